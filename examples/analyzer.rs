@@ -0,0 +1,154 @@
+// Pluggable checks that turn source text into diagnostics
+use std::path::Path;
+
+use crate::diagnostics::Diagnostic;
+use crate::error;
+use crate::markers::MarkerScanner;
+use crate::syntax::SyntaxChecker;
+use crate::FileProcessor;
+
+/// A check that reports diagnostics for a single file
+///
+/// Implement this to add custom feedback and register it with an
+/// `AnalyzerPipeline`.
+pub trait Analyzer {
+    /// Short identifier attached to every diagnostic this analyzer reports
+    fn name(&self) -> &str;
+
+    /// Checks one file's source, returning any problems found
+    fn analyze(&self, path: &Path, source: &str) -> Vec<Diagnostic>;
+}
+
+/// Runs a set of analyzers over files and collects their diagnostics
+#[derive(Default)]
+pub struct AnalyzerPipeline {
+    analyzers: Vec<Box<dyn Analyzer>>,
+}
+
+impl AnalyzerPipeline {
+    /// Creates a pipeline with no analyzers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a pipeline with the built-in syntax and marker checks
+    pub fn builtin() -> Self {
+        Self::new()
+            .with_analyzer(SyntaxChecker)
+            .with_analyzer(MarkerScanner::new())
+    }
+
+    /// Adds an analyzer, run after those already registered
+    pub fn with_analyzer<A: Analyzer + 'static>(mut self, analyzer: A) -> Self {
+        self.analyzers.push(Box::new(analyzer));
+        self
+    }
+
+    /// Names of the registered analyzers, in run order
+    pub fn names(&self) -> Vec<&str> {
+        self.analyzers.iter().map(|analyzer| analyzer.name()).collect()
+    }
+
+    /// Runs every analyzer over one file
+    ///
+    /// Each diagnostic has `analyzer` set to the name of the analyzer that
+    /// reported it.
+    pub fn run(&self, path: &Path, source: &str) -> Vec<Diagnostic> {
+        self.analyzers
+            .iter()
+            .flat_map(|analyzer| {
+                analyzer.analyze(path, source).into_iter().map(|mut diagnostic| {
+                    diagnostic.analyzer = Some(analyzer.name().to_string());
+                    diagnostic
+                })
+            })
+            .collect()
+    }
+
+    /// Runs every analyzer over the text files under a directory
+    ///
+    /// Files are walked and binary files skipped as in
+    /// `FileProcessor::process_dir`.
+    pub fn run_dir<P: AsRef<Path>>(
+        &self,
+        processor: &mut FileProcessor,
+        dir: P,
+        pattern: &str,
+    ) -> error::Result<Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
+        for file in processor.list_files(dir, pattern)? {
+            if processor.is_probably_binary(&file)? {
+                processor.skipped_files.push(crate::slash_path(&file));
+                continue;
+            }
+            let source = processor.read_file(&file)?;
+            diagnostics.extend(self.run(&file, &source));
+        }
+        Ok(diagnostics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Level;
+    use crate::position::Position;
+
+    struct LongFileAnalyzer {
+        max_lines: usize,
+    }
+
+    impl Analyzer for LongFileAnalyzer {
+        fn name(&self) -> &str {
+            "long-file"
+        }
+
+        fn analyze(&self, path: &Path, source: &str) -> Vec<Diagnostic> {
+            let lines = source.lines().count();
+            if lines <= self.max_lines {
+                return Vec::new();
+            }
+            vec![Diagnostic {
+                level: Level::Warning,
+                message: format!("file has {} lines", lines),
+                file: path.display().to_string(),
+                position: Position::new(1, 1),
+                span_text: String::new(),
+                code: None,
+                analyzer: None,
+            }]
+        }
+    }
+
+    #[test]
+    fn test_custom_analyzer_in_pipeline() {
+        let pipeline = AnalyzerPipeline::builtin().with_analyzer(LongFileAnalyzer { max_lines: 2 });
+        assert_eq!(pipeline.names(), vec!["syntax", "markers", "long-file"]);
+
+        let source = "fn main() {\n    // TODO: run\n}\n";
+        let diagnostics = pipeline.run(Path::new("src/main.rs"), source);
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.analyzer.as_deref().unwrap(), d.level, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("markers", Level::Note, "TODO: run"),
+                ("long-file", Level::Warning, "file has 3 lines"),
+            ]
+        );
+        assert_eq!(diagnostics[0].position, Position::new(2, 8));
+    }
+
+    #[test]
+    fn test_builtin_syntax_check() {
+        let diagnostics = AnalyzerPipeline::builtin().run(Path::new("lib.rs"), "fn main() {\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].level, Level::Error);
+        assert_eq!(diagnostics[0].analyzer.as_deref(), Some("syntax"));
+
+        // Built-in checks only apply to Rust files
+        assert!(AnalyzerPipeline::builtin().run(Path::new("notes.md"), "fn main() {\n").is_empty());
+    }
+}
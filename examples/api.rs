@@ -0,0 +1,251 @@
+// Extraction of a Rust file's public API surface and its documentation coverage
+use crate::syntax::{self, SyntaxError};
+
+/// What kind of item an API entry is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ApiItemKind {
+    Module,
+    Struct,
+    Enum,
+    Trait,
+    Function,
+    Const,
+    TypeAlias,
+}
+
+/// A `pub` item declared in a source file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiItem {
+    pub name: String,
+    pub kind: ApiItemKind,
+    pub line: u32,
+    /// The type an associated item is implemented on, such as `Person` for `Person::new`
+    pub owner: Option<String>,
+}
+
+/// Lists the `pub` items in Rust source, in source order
+///
+/// Items inside `pub mod` blocks are included, as are `pub` functions and
+/// constants in inherent `impl` blocks. Trait implementations are skipped
+/// because their items are public only through the trait.
+pub fn public_items(source: &str) -> Result<Vec<ApiItem>, SyntaxError> {
+    let file = syntax::parse_rust(source)?;
+    let mut items = Vec::new();
+    collect_items(&file.items, &mut items);
+    items.sort_by_key(|item| item.line);
+    Ok(items)
+}
+
+fn collect_items(items: &[syn::Item], out: &mut Vec<ApiItem>) {
+    for item in items {
+        let (vis, ident, kind) = match item {
+            syn::Item::Struct(item) => (&item.vis, &item.ident, ApiItemKind::Struct),
+            syn::Item::Enum(item) => (&item.vis, &item.ident, ApiItemKind::Enum),
+            syn::Item::Trait(item) => (&item.vis, &item.ident, ApiItemKind::Trait),
+            syn::Item::Fn(item) => (&item.vis, &item.sig.ident, ApiItemKind::Function),
+            syn::Item::Const(item) => (&item.vis, &item.ident, ApiItemKind::Const),
+            syn::Item::Type(item) => (&item.vis, &item.ident, ApiItemKind::TypeAlias),
+            syn::Item::Mod(item) => {
+                if is_public(&item.vis) {
+                    out.push(api_item(&item.ident, ApiItemKind::Module, None));
+                    if let Some((_, nested)) = &item.content {
+                        collect_items(nested, out);
+                    }
+                }
+                continue;
+            }
+            syn::Item::Impl(item) => {
+                collect_impl_items(item, out);
+                continue;
+            }
+            _ => continue,
+        };
+        if is_public(vis) {
+            out.push(api_item(ident, kind, None));
+        }
+    }
+}
+
+fn collect_impl_items(item: &syn::ItemImpl, out: &mut Vec<ApiItem>) {
+    let syn::Type::Path(self_type) = &*item.self_ty else {
+        return;
+    };
+    if item.trait_.is_some() {
+        return;
+    }
+    let owner = self_type.path.segments.last().map(|s| s.ident.to_string());
+    for impl_item in &item.items {
+        let (vis, ident, kind) = match impl_item {
+            syn::ImplItem::Fn(f) => (&f.vis, &f.sig.ident, ApiItemKind::Function),
+            syn::ImplItem::Const(c) => (&c.vis, &c.ident, ApiItemKind::Const),
+            _ => continue,
+        };
+        if is_public(vis) {
+            out.push(api_item(ident, kind, owner.clone()));
+        }
+    }
+}
+
+/// A `pub` item with no doc comment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndocumentedItem {
+    /// Item name, with methods named `Type::method`
+    pub name: String,
+    pub kind: ApiItemKind,
+    pub line: u32,
+}
+
+/// Lists `pub` functions, structs, enums, and traits without a doc comment
+///
+/// Both `///` and `/** */` comments count, as does a `#[doc]` attribute.
+/// Items are found as in `public_items`, so methods of inherent `impl`
+/// blocks are checked too. Results are in source order.
+pub fn missing_docs(source: &str) -> Result<Vec<UndocumentedItem>, SyntaxError> {
+    let file = syntax::parse_rust(source)?;
+    let mut items = Vec::new();
+    collect_undocumented(&file.items, &mut items);
+    items.sort_by_key(|item| item.line);
+    Ok(items)
+}
+
+fn collect_undocumented(items: &[syn::Item], out: &mut Vec<UndocumentedItem>) {
+    for item in items {
+        let (vis, attrs, ident, kind) = match item {
+            syn::Item::Struct(item) => (&item.vis, &item.attrs, &item.ident, ApiItemKind::Struct),
+            syn::Item::Enum(item) => (&item.vis, &item.attrs, &item.ident, ApiItemKind::Enum),
+            syn::Item::Trait(item) => (&item.vis, &item.attrs, &item.ident, ApiItemKind::Trait),
+            syn::Item::Fn(item) => (&item.vis, &item.attrs, &item.sig.ident, ApiItemKind::Function),
+            syn::Item::Mod(item) => {
+                if let (true, Some((_, nested))) = (is_public(&item.vis), &item.content) {
+                    collect_undocumented(nested, out);
+                }
+                continue;
+            }
+            syn::Item::Impl(item) => {
+                collect_undocumented_methods(item, out);
+                continue;
+            }
+            _ => continue,
+        };
+        if is_public(vis) && !has_docs(attrs) {
+            out.push(undocumented(ident.to_string(), ident, kind));
+        }
+    }
+}
+
+fn collect_undocumented_methods(item: &syn::ItemImpl, out: &mut Vec<UndocumentedItem>) {
+    let syn::Type::Path(self_type) = &*item.self_ty else {
+        return;
+    };
+    let Some(owner) = self_type.path.segments.last().filter(|_| item.trait_.is_none()) else {
+        return;
+    };
+    for impl_item in &item.items {
+        if let syn::ImplItem::Fn(f) = impl_item {
+            if is_public(&f.vis) && !has_docs(&f.attrs) {
+                let name = format!("{}::{}", owner.ident, f.sig.ident);
+                out.push(undocumented(name, &f.sig.ident, ApiItemKind::Function));
+            }
+        }
+    }
+}
+
+fn has_docs(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| attr.path().is_ident("doc"))
+}
+
+fn undocumented(name: String, ident: &syn::Ident, kind: ApiItemKind) -> UndocumentedItem {
+    UndocumentedItem {
+        name,
+        kind,
+        line: ident.span().start().line as u32,
+    }
+}
+
+/// Checks for plain `pub`, treating `pub(crate)` and narrower as private
+fn is_public(vis: &syn::Visibility) -> bool {
+    matches!(vis, syn::Visibility::Public(_))
+}
+
+fn api_item(ident: &syn::Ident, kind: ApiItemKind, owner: Option<String>) -> ApiItem {
+    ApiItem {
+        name: ident.to_string(),
+        kind,
+        line: ident.span().start().line as u32,
+        owner,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find<'a>(items: &'a [ApiItem], owner: Option<&str>, name: &str) -> Option<&'a ApiItem> {
+        items
+            .iter()
+            .find(|item| item.name == name && item.owner.as_deref() == owner)
+    }
+
+    #[test]
+    fn test_public_items_of_example() {
+        let items = public_items(include_str!("example.rs")).unwrap();
+
+        assert_eq!(find(&items, None, "Person").unwrap().kind, ApiItemKind::Struct);
+        assert_eq!(find(&items, None, "Greeter").unwrap().kind, ApiItemKind::Trait);
+        assert_eq!(find(&items, None, "FileProcessor").unwrap().kind, ApiItemKind::Struct);
+        assert_eq!(find(&items, None, "diagnostics").unwrap().kind, ApiItemKind::Module);
+        assert_eq!(find(&items, None, "ADULT_AGE").unwrap().kind, ApiItemKind::Const);
+        assert_eq!(find(&items, Some("Person"), "new").unwrap().kind, ApiItemKind::Function);
+        assert!(find(&items, Some("FileProcessor"), "read_file").is_some());
+
+        // Private helpers, trait impl methods, and `main` aren't public API
+        assert!(find(&items, Some("FileProcessor"), "resolve_path").is_none());
+        assert!(find(&items, None, "temp_sibling").is_none());
+        assert!(find(&items, None, "main").is_none());
+        assert!(find(&items, Some("Person"), "greet").is_none());
+        assert!(items.windows(2).all(|pair| pair[0].line <= pair[1].line));
+    }
+
+    #[test]
+    fn test_public_items_in_nested_module() {
+        let source = "pub mod outer {\n    pub struct Visible;\n    struct Hidden;\n}\nmod private {\n    pub fn unreachable() {}\n}\npub(crate) fn internal() {}\n";
+        assert_eq!(
+            public_items(source).unwrap(),
+            vec![
+                ApiItem { name: "outer".to_string(), kind: ApiItemKind::Module, line: 1, owner: None },
+                ApiItem { name: "Visible".to_string(), kind: ApiItemKind::Struct, line: 2, owner: None },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_docs() {
+        let source = r#"/// Adds two numbers
+pub fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+pub fn subtract(a: u32, b: u32) -> u32 {
+    a - b
+}
+
+fn private_helper() {}
+
+/** Block-documented */
+pub struct Point;
+
+impl Point {
+    pub fn origin() -> Self {
+        Point
+    }
+}
+"#;
+        assert_eq!(
+            missing_docs(source).unwrap(),
+            vec![
+                UndocumentedItem { name: "subtract".to_string(), kind: ApiItemKind::Function, line: 6 },
+                UndocumentedItem { name: "Point::origin".to_string(), kind: ApiItemKind::Function, line: 16 },
+            ]
+        );
+    }
+}
@@ -0,0 +1,164 @@
+// Detecting which files changed since a previous run
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::error::{self, FileProcessorError};
+use crate::{slash_path, FileProcessor};
+
+/// Remembers file hashes in a JSON store so later runs can skip unchanged files
+///
+/// The store maps paths relative to the processor's base path to their
+/// SHA-256 digests.
+#[derive(Debug, Clone)]
+pub struct ChangeTracker {
+    store: PathBuf,
+    hashes: BTreeMap<String, String>,
+}
+
+impl ChangeTracker {
+    /// Loads the hashes saved at the store path, starting empty if it doesn't exist
+    pub fn new<P: AsRef<Path>>(store: P) -> error::Result<Self> {
+        let store = store.as_ref().to_path_buf();
+        let hashes = match fs::read_to_string(&store) {
+            Ok(json) => serde_json::from_str(&json).map_err(|e| store_error(e, &store))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(FileProcessorError::from_io(e, &store)),
+        };
+        Ok(ChangeTracker { store, hashes })
+    }
+
+    /// Returns the files under a directory whose hash differs from the stored one
+    ///
+    /// Files are listed as in `FileProcessor::list_files`, so on the first run
+    /// every file is reported. The store is updated and saved afterwards, and
+    /// entries for files that no longer exist are dropped. The store itself
+    /// is skipped if it lies under the directory, since it changes every run.
+    /// If any file can't be hashed, the stored hashes are left untouched.
+    pub fn changed_files<P: AsRef<Path>>(
+        &mut self,
+        processor: &FileProcessor,
+        dir: P,
+    ) -> error::Result<Vec<PathBuf>> {
+        self.changed_files_with(processor, dir.as_ref(), |file| processor.file_hash(file))
+    }
+
+    fn changed_files_with<F>(
+        &mut self,
+        processor: &FileProcessor,
+        dir: &Path,
+        hash: F,
+    ) -> error::Result<Vec<PathBuf>>
+    where
+        F: Fn(&Path) -> error::Result<String>,
+    {
+        let store = fs::canonicalize(&self.store).ok();
+        let mut changed = Vec::new();
+        let mut updated = BTreeMap::new();
+        for file in processor.list_files(dir, "*")? {
+            if store.is_some() && processor.resolve_path(&file).ok() == store {
+                continue;
+            }
+            let hash = hash(&file)?;
+            if self.hashes.get(&slash_path(&file)) != Some(&hash) {
+                updated.insert(slash_path(&file), hash);
+                changed.push(file);
+            }
+        }
+        self.hashes.extend(updated);
+        self.hashes.retain(|path, _| processor.is_file(path));
+        self.save()?;
+        Ok(changed)
+    }
+
+    fn save(&self) -> error::Result<()> {
+        let json = serde_json::to_string_pretty(&self.hashes).map_err(|e| store_error(e, &self.store))?;
+        fs::write(&self.store, json).map_err(|e| FileProcessorError::from_io(e, &self.store))
+    }
+}
+
+/// Wraps a serde_json failure as an IO error naming the store
+fn store_error(err: serde_json::Error, store: &Path) -> FileProcessorError {
+    let message = format!("invalid change store {}: {}", store.display(), err);
+    FileProcessorError::from_io(io::Error::new(io::ErrorKind::InvalidData, message), store)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_files_across_runs() {
+        let dir = std::env::temp_dir().join(format!("changes_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/a.rs"), "fn a() {}").unwrap();
+        fs::write(dir.join("src/b.rs"), "fn b() {}").unwrap();
+        let store = dir.join("hashes.json");
+        let processor = FileProcessor::new(&dir);
+
+        let mut tracker = ChangeTracker::new(&store).unwrap();
+        let first = tracker.changed_files(&processor, "src").unwrap();
+        assert_eq!(first, vec![PathBuf::from("src/a.rs"), PathBuf::from("src/b.rs")]);
+
+        fs::write(dir.join("src/b.rs"), "fn b() { todo!() }").unwrap();
+        let mut tracker = ChangeTracker::new(&store).unwrap();
+        let second = tracker.changed_files(&processor, "src").unwrap();
+        assert_eq!(second, vec![PathBuf::from("src/b.rs")]);
+        assert!(tracker.changed_files(&processor, "src").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_store_inside_scanned_dir_is_skipped() {
+        let dir = std::env::temp_dir().join(format!("changes_store_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.rs"), "fn a() {}").unwrap();
+        let processor = FileProcessor::new(&dir);
+
+        let mut tracker = ChangeTracker::new(dir.join("hashes.json")).unwrap();
+        assert_eq!(tracker.changed_files(&processor, ".").unwrap(), vec![PathBuf::from("./a.rs")]);
+        assert!(tracker.changed_files(&processor, ".").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_corrupt_store_names_file() {
+        let dir = std::env::temp_dir().join(format!("changes_corrupt_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("hashes.json"), "{not json").unwrap();
+
+        let err = ChangeTracker::new(dir.join("hashes.json")).unwrap_err();
+        assert!(err.to_string().contains("hashes.json"), "{}", err);
+    }
+
+    #[test]
+    fn test_failed_hash_keeps_changes_pending() {
+        let dir = std::env::temp_dir().join(format!("changes_failed_hash_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.rs"), "fn a() {}").unwrap();
+        fs::write(dir.join("b.rs"), "fn b() {}").unwrap();
+        let store = std::env::temp_dir().join(format!("changes_failed_hash_{}.json", std::process::id()));
+        let _ = fs::remove_file(&store);
+        let processor = FileProcessor::new(&dir);
+        let mut tracker = ChangeTracker::new(&store).unwrap();
+
+        let unreadable_b = |file: &Path| {
+            if file.ends_with("b.rs") {
+                Err(FileProcessorError::PermissionDenied(file.to_path_buf()))
+            } else {
+                processor.file_hash(file)
+            }
+        };
+        assert!(matches!(
+            tracker.changed_files_with(&processor, Path::new("."), unreadable_b),
+            Err(FileProcessorError::PermissionDenied(_))
+        ));
+        assert!(!store.exists());
+
+        let changed = tracker.changed_files(&processor, ".").unwrap();
+        assert_eq!(changed, vec![PathBuf::from("./a.rs"), PathBuf::from("./b.rs")]);
+    }
+}
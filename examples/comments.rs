@@ -0,0 +1,103 @@
+// Removing comments from source while keeping every position in place
+use crate::language::Language;
+use crate::lexer;
+use crate::metrics::CommentSyntax;
+
+/// Blanks out line and block comments, leaving string literals untouched
+///
+/// Each character of a comment becomes a space, while line breaks are kept,
+/// so lines and columns in the result match the original. Rust, JavaScript,
+/// TypeScript, and Go use the Rust-style lexer, which understands raw strings
+/// and escaped quotes; other languages use their `CommentSyntax` with `'` and
+/// `"` strings. Unknown languages are returned unchanged.
+pub fn strip_comments(source: &str, language: Language) -> String {
+    let comments = match language {
+        Language::Rust | Language::JavaScript | Language::TypeScript | Language::Go => lexer::segments(source)
+            .into_iter()
+            .filter(|segment| segment.kind.is_comment())
+            .map(|segment| (segment.start, segment.end))
+            .collect(),
+        Language::Python => comment_ranges(source, &language.into(), &['"', '\'']),
+        Language::Markdown => comment_ranges(source, &language.into(), &[]),
+        Language::Unknown => Vec::new(),
+    };
+
+    let mut stripped = String::with_capacity(source.len());
+    let mut copied = 0;
+    for (start, end) in comments {
+        stripped.push_str(&source[copied..start]);
+        stripped.extend(source[start..end].chars().map(|c| if c == '\n' || c == '\r' { c } else { ' ' }));
+        copied = end;
+    }
+    stripped.push_str(&source[copied..]);
+    stripped
+}
+
+/// Finds comment byte ranges, skipping over strings delimited by any of `quotes`
+fn comment_ranges(source: &str, syntax: &CommentSyntax, quotes: &[char]) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while let Some(c) = source[i..].chars().next() {
+        let rest = &source[i..];
+        let end = if quotes.contains(&c) {
+            string_end(source, i, c)
+        } else if let Some((open, close)) = syntax.block.iter().find(|(open, _)| rest.starts_with(open.as_str())) {
+            let end = rest[open.len()..]
+                .find(close.as_str())
+                .map(|index| i + open.len() + index + close.len())
+                .unwrap_or(source.len());
+            ranges.push((i, end));
+            end
+        } else if syntax.line.iter().any(|marker| rest.starts_with(marker.as_str())) {
+            let end = rest.find('\n').map(|index| i + index).unwrap_or(source.len());
+            ranges.push((i, end));
+            end
+        } else {
+            i + c.len_utf8()
+        };
+        i = end;
+    }
+    ranges
+}
+
+/// Finds the end of a string opened at `start`, which also ends at a line break
+fn string_end(source: &str, start: usize, quote: char) -> usize {
+    let mut chars = source[start + quote.len_utf8()..].char_indices();
+    while let Some((index, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c == quote || c == '\n' {
+            return start + quote.len_utf8() + index + c.len_utf8();
+        }
+    }
+    source.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_line_comment() {
+        let source = "let x = 1; // set x\nlet y = 2;\n";
+        assert_eq!(strip_comments(source, Language::Rust), "let x = 1;         \nlet y = 2;\n");
+    }
+
+    #[test]
+    fn test_strip_block_comment_keeps_lines() {
+        let source = "a /* first\n   second */ b\n";
+        let stripped = strip_comments(source, Language::Rust);
+        assert_eq!(stripped, "a         \n             b\n");
+        assert_eq!(stripped.lines().count(), source.lines().count());
+        assert_eq!(stripped.find('b'), source.rfind('b'));
+    }
+
+    #[test]
+    fn test_comment_like_strings_are_untouched() {
+        let source = "let s = \"// not a comment\"; let r = r#\"say \"/*\" ok\"#; let e = \"\\\"//\";\n";
+        assert_eq!(strip_comments(source, Language::Rust), source);
+
+        let python = "url = 'http://x' # real\ns = \"# not\"\n";
+        assert_eq!(strip_comments(python, Language::Python), "url = 'http://x'       \ns = \"# not\"\n");
+    }
+}
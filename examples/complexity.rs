@@ -0,0 +1,295 @@
+// Function-level metrics for Rust source: cyclomatic complexity, length, and location
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+
+use crate::syntax::{self, SyntaxError};
+
+/// Estimated cyclomatic complexity of a single function
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionComplexity {
+    pub name: String,
+    pub complexity: u32,
+    pub line: u32,
+}
+
+/// Estimates the cyclomatic complexity of every function in Rust source
+///
+/// Each function starts at 1 and gains a point for every `if`, `while`,
+/// `for`, `&&`, `||`, and `?`, and for each `match` arm after the first.
+/// Methods are named `Type::method`, and closures count towards the function
+/// that contains them while nested functions are reported on their own.
+pub fn complexity(source: &str) -> Result<Vec<FunctionComplexity>, SyntaxError> {
+    Ok(functions(source)?
+        .into_iter()
+        .map(|f| FunctionComplexity {
+            name: f.name,
+            complexity: f.complexity,
+            line: f.line,
+        })
+        .collect())
+}
+
+/// The length of a single function
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionLength {
+    pub name: String,
+    pub line_count: u32,
+    pub start_line: u32,
+}
+
+/// Finds functions spanning more than `max_lines` lines
+///
+/// A function runs from the line its signature starts on to the line of its
+/// closing brace. Attributes and doc comments above the signature aren't
+/// counted, while comments and blank lines inside the body are. Functions
+/// are named as in `complexity`.
+pub fn long_functions(source: &str, max_lines: usize) -> Result<Vec<FunctionLength>, SyntaxError> {
+    Ok(functions(source)?
+        .into_iter()
+        .filter(|f| f.line_count as usize > max_lines)
+        .map(|f| FunctionLength {
+            name: f.name,
+            line_count: f.line_count,
+            start_line: f.start_line,
+        })
+        .collect())
+}
+
+/// Names the innermost function containing a 1-based line
+///
+/// Functions span lines as in `long_functions` and are named as in
+/// `complexity`, so a line inside a method gives `Type::method`. Returns
+/// `None` for lines outside any function or when the source doesn't parse.
+pub fn function_at_line(source: &str, line: usize) -> Option<String> {
+    functions(source)
+        .ok()?
+        .into_iter()
+        .filter(|f| (f.start_line as usize..(f.start_line + f.line_count) as usize).contains(&line))
+        .max_by_key(|f| f.start_line)
+        .map(|f| f.name)
+}
+
+/// Everything measured about one function, in source order
+fn functions(source: &str) -> Result<Vec<FunctionInfo>, SyntaxError> {
+    let file = syntax::parse_rust(source)?;
+    let mut visitor = FunctionVisitor::default();
+    visitor.visit_file(&file);
+    visitor.functions.sort_by_key(|f| f.line);
+    Ok(visitor.functions)
+}
+
+struct FunctionInfo {
+    name: String,
+    /// Line of the function's name
+    line: u32,
+    start_line: u32,
+    line_count: u32,
+    complexity: u32,
+}
+
+/// Finds functions and measures each one's body
+#[derive(Default)]
+struct FunctionVisitor {
+    functions: Vec<FunctionInfo>,
+    self_type: Option<String>,
+}
+
+impl FunctionVisitor {
+    fn record(&mut self, sig: &syn::Signature, block: &syn::Block, qualify: bool) {
+        let name = match (&self.self_type, qualify) {
+            (Some(self_type), true) => format!("{}::{}", self_type, sig.ident),
+            _ => sig.ident.to_string(),
+        };
+        let mut counter = DecisionCounter { count: 1 };
+        counter.visit_block(block);
+        let start_line = sig.span().start().line as u32;
+        let end_line = block.brace_token.span.close().end().line as u32;
+        self.functions.push(FunctionInfo {
+            name,
+            line: sig.ident.span().start().line as u32,
+            start_line,
+            line_count: end_line - start_line + 1,
+            complexity: counter.count,
+        });
+    }
+}
+
+impl<'ast> Visit<'ast> for FunctionVisitor {
+    fn visit_item_fn(&mut self, item: &'ast syn::ItemFn) {
+        self.record(&item.sig, &item.block, false);
+        visit::visit_item_fn(self, item);
+    }
+
+    fn visit_item_impl(&mut self, item: &'ast syn::ItemImpl) {
+        let self_type = match &*item.self_ty {
+            syn::Type::Path(path) => path.path.segments.last().map(|s| s.ident.to_string()),
+            _ => None,
+        };
+        let outer = std::mem::replace(&mut self.self_type, self_type);
+        visit::visit_item_impl(self, item);
+        self.self_type = outer;
+    }
+
+    fn visit_item_trait(&mut self, item: &'ast syn::ItemTrait) {
+        let outer = self.self_type.replace(item.ident.to_string());
+        visit::visit_item_trait(self, item);
+        self.self_type = outer;
+    }
+
+    fn visit_impl_item_fn(&mut self, item: &'ast syn::ImplItemFn) {
+        self.record(&item.sig, &item.block, true);
+        visit::visit_impl_item_fn(self, item);
+    }
+
+    fn visit_trait_item_fn(&mut self, item: &'ast syn::TraitItemFn) {
+        if let Some(block) = &item.default {
+            self.record(&item.sig, block, true);
+        }
+        visit::visit_trait_item_fn(self, item);
+    }
+}
+
+/// Counts decision points within one function body, skipping nested items
+struct DecisionCounter {
+    count: u32,
+}
+
+impl<'ast> Visit<'ast> for DecisionCounter {
+    fn visit_item(&mut self, _item: &'ast syn::Item) {
+        // Nested functions and impls are scored separately
+    }
+
+    fn visit_expr_if(&mut self, expr: &'ast syn::ExprIf) {
+        self.count += 1;
+        visit::visit_expr_if(self, expr);
+    }
+
+    fn visit_expr_match(&mut self, expr: &'ast syn::ExprMatch) {
+        self.count += expr.arms.len().saturating_sub(1) as u32;
+        visit::visit_expr_match(self, expr);
+    }
+
+    fn visit_expr_while(&mut self, expr: &'ast syn::ExprWhile) {
+        self.count += 1;
+        visit::visit_expr_while(self, expr);
+    }
+
+    fn visit_expr_for_loop(&mut self, expr: &'ast syn::ExprForLoop) {
+        self.count += 1;
+        visit::visit_expr_for_loop(self, expr);
+    }
+
+    fn visit_expr_binary(&mut self, expr: &'ast syn::ExprBinary) {
+        if matches!(expr.op, syn::BinOp::And(_) | syn::BinOp::Or(_)) {
+            self.count += 1;
+        }
+        visit::visit_expr_binary(self, expr);
+    }
+
+    fn visit_expr_try(&mut self, expr: &'ast syn::ExprTry) {
+        self.count += 1;
+        visit::visit_expr_try(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simple_function() {
+        let functions = complexity("fn add(a: u32, b: u32) -> u32 {\n    a + b\n}\n").unwrap();
+        assert_eq!(
+            functions,
+            vec![FunctionComplexity { name: "add".to_string(), complexity: 1, line: 1 }]
+        );
+    }
+
+    #[test]
+    fn test_branchy_function() {
+        let source = r#"
+struct Parser;
+
+impl Parser {
+    fn parse(&self, input: &str) -> Result<u32, String> {
+        let mut total = 0;
+        for part in input.split(',') {
+            if part.is_empty() || part == "-" {
+                continue;
+            } else if part.starts_with('#') && part.len() > 1 {
+                total += 1;
+            }
+            total += match part.len() {
+                1 => 1,
+                2 => 2,
+                _ => part.parse::<u32>().map_err(|e| e.to_string())?,
+            };
+        }
+        while total > 100 {
+            total /= 2;
+        }
+        Ok(total)
+    }
+}
+
+fn helper() {}
+"#;
+        // 1 + for + 2 ifs + || + && + 2 extra match arms + ? + while
+        let functions = complexity(source).unwrap();
+        assert_eq!(
+            functions,
+            vec![
+                FunctionComplexity { name: "Parser::parse".to_string(), complexity: 10, line: 5 },
+                FunctionComplexity { name: "helper".to_string(), complexity: 1, line: 26 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_long_functions() {
+        let body: String = (0..12).map(|i| format!("    let x{} = {};\n", i, i)).collect();
+        let source = format!(
+            "/// Short and sweet\n#[inline]\nfn short() -> u32 {{\n    1\n}}\n\n/// Far too long\nfn long() {{\n{}}}\n",
+            body
+        );
+
+        assert_eq!(
+            long_functions(&source, 10).unwrap(),
+            vec![FunctionLength { name: "long".to_string(), line_count: 14, start_line: 8 }]
+        );
+        // `short` spans three lines; its doc comment and attribute aren't counted
+        assert!(long_functions(&source, 14).unwrap().is_empty());
+        assert_eq!(long_functions(&source, 2).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_source() {
+        assert!(complexity("fn broken( {").is_err());
+    }
+
+    #[test]
+    fn test_function_at_line() {
+        let source = r#"use std::fmt;
+
+struct Person {
+    name: String,
+}
+
+impl Person {
+    /// Says hello
+    pub fn greet(&self) -> String {
+        fn shout(text: &str) -> String {
+            text.to_uppercase()
+        }
+        format!("Hello, {}!", shout(&self.name))
+    }
+}
+"#;
+        assert_eq!(function_at_line(source, 9).as_deref(), Some("Person::greet"));
+        assert_eq!(function_at_line(source, 13).as_deref(), Some("Person::greet"));
+        assert_eq!(function_at_line(source, 11).as_deref(), Some("shout"));
+        assert_eq!(function_at_line(source, 8), None);
+        assert_eq!(function_at_line(source, 1), None);
+        assert_eq!(function_at_line("fn broken(", 1), None);
+    }
+}
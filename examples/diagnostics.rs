@@ -0,0 +1,330 @@
+// Structured compiler and clippy diagnostics from cargo's JSON output
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::Value;
+
+use crate::position::Position;
+
+/// Severity of a diagnostic, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(rename_all = "lowercase"))]
+pub enum Level {
+    Help,
+    Note,
+    Warning,
+    Error,
+}
+
+impl Level {
+    /// Parses a level as reported by rustc
+    pub fn from_name(name: &str) -> Option<Level> {
+        match name {
+            "help" => Some(Level::Help),
+            "note" | "failure-note" => Some(Level::Note),
+            "warning" => Some(Level::Warning),
+            "error" | "error: internal compiler error" => Some(Level::Error),
+            _ => None,
+        }
+    }
+}
+
+/// A single compiler diagnostic located at its primary span
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Diagnostic {
+    pub level: Level,
+    pub message: String,
+    pub file: String,
+    pub position: Position,
+    pub span_text: String,
+    /// Error or lint code such as `E0308`, when the compiler reported one
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub code: Option<String>,
+    /// Name of the analyzer that reported this, or `None` for compiler output
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub analyzer: Option<String>,
+}
+
+/// A collection of diagnostics that can be filtered by severity
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl Diagnostics {
+    /// Keeps only diagnostics at or above the given level
+    pub fn filter_by_level(&self, min: Level) -> Diagnostics {
+        Diagnostics(self.0.iter().filter(|d| d.level >= min).cloned().collect())
+    }
+
+    /// Groups diagnostics by their code, leaving out those without one
+    pub fn group_by_code(&self) -> HashMap<String, Vec<&Diagnostic>> {
+        let mut groups: HashMap<String, Vec<&Diagnostic>> = HashMap::new();
+        for diagnostic in &self.0 {
+            if let Some(code) = &diagnostic.code {
+                groups.entry(code.clone()).or_default().push(diagnostic);
+            }
+        }
+        groups
+    }
+
+    /// Checks whether any diagnostic is an error
+    pub fn has_errors(&self) -> bool {
+        self.0.iter().any(|d| d.level == Level::Error)
+    }
+}
+
+impl From<Vec<Diagnostic>> for Diagnostics {
+    fn from(diagnostics: Vec<Diagnostic>) -> Self {
+        Diagnostics(diagnostics)
+    }
+}
+
+/// Runs `cargo check` in a crate directory and collects its diagnostics
+///
+/// Fails with the captured stderr when cargo exits unsuccessfully without
+/// reporting any compiler messages, such as when there's no `Cargo.toml`.
+pub fn run_cargo_check(manifest_dir: &Path) -> io::Result<Vec<Diagnostic>> {
+    let messages = run_cargo(manifest_dir, &["check", "--message-format=json"])?;
+    Ok(messages.iter().filter_map(parse_diagnostic).collect())
+}
+
+/// Runs `cargo clippy` in a crate directory and collects its diagnostics
+///
+/// Lints in `allow` are passed as `-A` and lints in `deny` as `-D`, using
+/// names like `clippy::needless_return`. Lint names are reported in each
+/// diagnostic's `code`. Fails with `NotFound` when clippy isn't installed
+/// and with `InvalidInput` when a lint name isn't recognized.
+pub fn run_clippy(manifest_dir: &Path, allow: &[String], deny: &[String]) -> io::Result<Vec<Diagnostic>> {
+    let mut args = vec!["clippy", "--message-format=json", "--"];
+    for lint in allow {
+        args.extend(["-A", lint]);
+    }
+    for lint in deny {
+        args.extend(["-D", lint]);
+    }
+    let messages = run_cargo(manifest_dir, &args)?;
+    // rustc only warns about unknown lints passed on the command line
+    if let Some(unknown) = messages.iter().find(|message| message["code"]["code"] == "E0602") {
+        let text = unknown["message"].as_str().unwrap_or("unknown lint");
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, text.to_string()));
+    }
+    Ok(messages.iter().filter_map(parse_diagnostic).collect())
+}
+
+/// Runs a cargo subcommand and returns its compiler messages
+fn run_cargo(manifest_dir: &Path, args: &[&str]) -> io::Result<Vec<Value>> {
+    let output = Command::new("cargo").args(args).current_dir(manifest_dir).output()?;
+    let messages = compiler_messages(&String::from_utf8_lossy(&output.stdout));
+    if !output.status.success() && messages.is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let kind = if stderr.contains("no such command") {
+            io::ErrorKind::NotFound
+        } else {
+            io::ErrorKind::Other
+        };
+        return Err(io::Error::new(kind, format!("cargo {} failed: {}", args[0], stderr.trim())));
+    }
+    Ok(messages)
+}
+
+/// Parses cargo's JSON message lines into diagnostics
+///
+/// Non-diagnostic messages such as `compiler-artifact` are skipped, as are
+/// diagnostics without a primary span like "N warnings emitted".
+pub fn parse_cargo_messages(output: &str) -> Vec<Diagnostic> {
+    compiler_messages(output).iter().filter_map(parse_diagnostic).collect()
+}
+
+/// Extracts the `message` of every `compiler-message` line in cargo's JSON output
+fn compiler_messages(output: &str) -> Vec<Value> {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|message| message["reason"] == "compiler-message")
+        .map(|mut message| message["message"].take())
+        .collect()
+}
+
+fn parse_diagnostic(message: &Value) -> Option<Diagnostic> {
+    let span = message["spans"]
+        .as_array()?
+        .iter()
+        .find(|span| span["is_primary"] == true)?;
+
+    Some(Diagnostic {
+        level: Level::from_name(message["level"].as_str()?)?,
+        message: message["message"].as_str()?.to_string(),
+        file: span["file_name"].as_str()?.to_string(),
+        position: Position::new(
+            span["line_start"].as_u64()? as u32,
+            span["column_start"].as_u64()? as u32,
+        ),
+        span_text: highlighted_text(span),
+        code: message["code"]["code"].as_str().map(str::to_string),
+        analyzer: None,
+    })
+}
+
+/// Extracts the highlighted portion of each source line in a span
+fn highlighted_text(span: &Value) -> String {
+    let Some(lines) = span["text"].as_array() else {
+        return String::new();
+    };
+    lines
+        .iter()
+        .filter_map(|line| {
+            let text = line["text"].as_str()?;
+            let start = line["highlight_start"].as_u64()?.saturating_sub(1) as usize;
+            let end = line["highlight_end"].as_u64()?.saturating_sub(1) as usize;
+            Some(text.chars().skip(start).take(end.saturating_sub(start)).collect::<String>())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_skips_non_diagnostic_messages() {
+        let output = concat!(
+            r#"{"reason":"compiler-artifact","target":{"name":"dep"}}"#, "\n",
+            r#"{"reason":"compiler-message","message":{"level":"error","message":"mismatched types","spans":[{"is_primary":true,"file_name":"src/main.rs","line_start":3,"column_start":18,"text":[{"text":"    let x: u32 = \"a\";","highlight_start":18,"highlight_end":21}]}]}}"#, "\n",
+            r#"{"reason":"compiler-message","message":{"level":"warning","message":"1 warning emitted","spans":[]}}"#, "\n",
+            r#"{"reason":"build-finished","success":false}"#, "\n",
+        );
+
+        let diagnostics = parse_cargo_messages(output);
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic {
+                level: Level::Error,
+                message: "mismatched types".to_string(),
+                file: "src/main.rs".to_string(),
+                position: Position::new(3, 18),
+                span_text: "\"a\"".to_string(),
+                code: None,
+                analyzer: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_run_cargo_check_on_fixture() {
+        let fixture = Path::new(file!())
+            .parent()
+            .unwrap()
+            .join("fixtures/warning_crate");
+        let diagnostics = run_cargo_check(&fixture).unwrap();
+
+        let unused = diagnostics
+            .iter()
+            .find(|d| d.message.contains("unused variable"))
+            .expect("fixture should report an unused variable");
+        assert_eq!(unused.level, Level::Warning);
+        assert_eq!(unused.file, "src/lib.rs");
+        assert_eq!(unused.position, Position::new(3, 9));
+        assert_eq!(unused.span_text, "unused");
+    }
+
+    #[test]
+    fn test_run_cargo_check_without_manifest() {
+        let dir = std::env::temp_dir().join(format!("no_manifest_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let err = run_cargo_check(&dir).unwrap_err();
+        assert!(err.to_string().contains("Cargo.toml"), "{}", err);
+    }
+
+    #[test]
+    fn test_run_clippy_on_fixture() {
+        let fixture = Path::new(file!())
+            .parent()
+            .unwrap()
+            .join("fixtures/clippy_crate");
+        let lint = "clippy::needless_return".to_string();
+
+        let diagnostics = match run_clippy(&fixture, &[], std::slice::from_ref(&lint)) {
+            Ok(diagnostics) => diagnostics,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                eprintln!("skipping: clippy not installed");
+                return;
+            }
+            Err(err) => panic!("unexpected error: {}", err),
+        };
+        let needless = diagnostics
+            .iter()
+            .find(|d| d.code.as_deref() == Some("clippy::needless_return"))
+            .expect("fixture should report a needless return");
+        assert_eq!(needless.level, Level::Error);
+        assert_eq!(needless.position, Position::new(3, 5));
+
+        let allowed = run_clippy(&fixture, &[lint], &[]).unwrap();
+        assert!(allowed.iter().all(|d| d.code.as_deref() != Some("clippy::needless_return")));
+
+        let err = run_clippy(&fixture, &[], &["clippy::not_a_lint".to_string()]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("clippy::not_a_lint"), "{}", err);
+    }
+
+    fn diagnostic(level: Level, message: &str) -> Diagnostic {
+        Diagnostic {
+            level,
+            message: message.to_string(),
+            file: "src/lib.rs".to_string(),
+            position: Position::new(1, 1),
+            span_text: String::new(),
+            code: None,
+            analyzer: None,
+        }
+    }
+
+    #[test]
+    fn test_filter_by_level() {
+        let diagnostics = Diagnostics::from(vec![
+            diagnostic(Level::Help, "help"),
+            diagnostic(Level::Error, "error"),
+            diagnostic(Level::Note, "note"),
+            diagnostic(Level::Warning, "warning"),
+        ]);
+
+        let messages = |d: Diagnostics| d.0.into_iter().map(|d| d.message).collect::<Vec<_>>();
+        assert_eq!(messages(diagnostics.filter_by_level(Level::Error)), vec!["error"]);
+        assert_eq!(messages(diagnostics.filter_by_level(Level::Warning)), vec!["error", "warning"]);
+        assert_eq!(diagnostics.filter_by_level(Level::Help).0.len(), 4);
+    }
+
+    #[test]
+    fn test_has_errors() {
+        let mut diagnostics = Diagnostics::from(vec![
+            diagnostic(Level::Warning, "warning"),
+            diagnostic(Level::Note, "note"),
+        ]);
+        assert!(!diagnostics.has_errors());
+        assert!(!diagnostics.filter_by_level(Level::Error).has_errors());
+
+        diagnostics.0.push(diagnostic(Level::Error, "error"));
+        assert!(diagnostics.has_errors());
+        assert!(Level::Help < Level::Note && Level::Note < Level::Warning);
+    }
+
+    #[test]
+    fn test_group_by_code() {
+        let fixture = Path::new(file!()).parent().unwrap().join("fixtures/coded_errors.jsonl");
+        let output = std::fs::read_to_string(fixture).unwrap();
+        let diagnostics = Diagnostics::from(parse_cargo_messages(&output));
+        assert_eq!(diagnostics.0.len(), 4);
+        assert_eq!(diagnostics.0[0].code.as_deref(), Some("E0308"));
+        assert_eq!(diagnostics.0[3].code, None);
+
+        let groups = diagnostics.group_by_code();
+        assert_eq!(groups.len(), 2);
+        let mismatches: Vec<_> = groups["E0308"].iter().map(|d| d.position.line).collect();
+        assert_eq!(mismatches, vec![3, 8]);
+        assert_eq!(groups["E0425"][0].message, "cannot find value `missing` in this scope");
+    }
+}
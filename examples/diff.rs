@@ -0,0 +1,48 @@
+// Line-based diffing using a longest common subsequence
+
+/// A single line of a diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    Context(String),
+    Added(String),
+    Removed(String),
+}
+
+/// Computes a minimal line diff turning `left` into `right`
+///
+/// Within a changed region removed lines are listed before added ones.
+pub fn diff_lines(left: &str, right: &str) -> Vec<DiffLine> {
+    let left: Vec<&str> = left.lines().collect();
+    let right: Vec<&str> = right.lines().collect();
+
+    // lcs[i][j] is the LCS length of left[i..] and right[j..]
+    let mut lcs = vec![vec![0usize; right.len() + 1]; left.len() + 1];
+    for i in (0..left.len()).rev() {
+        for j in (0..right.len()).rev() {
+            lcs[i][j] = if left[i] == right[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() && j < right.len() {
+        if left[i] == right[j] {
+            diff.push(DiffLine::Context(left[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            diff.push(DiffLine::Removed(left[i].to_string()));
+            i += 1;
+        } else {
+            diff.push(DiffLine::Added(right[j].to_string()));
+            j += 1;
+        }
+    }
+    diff.extend(left[i..].iter().map(|line| DiffLine::Removed(line.to_string())));
+    diff.extend(right[j..].iter().map(|line| DiffLine::Added(line.to_string())));
+    diff
+}
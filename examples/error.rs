@@ -0,0 +1,242 @@
+// Error types for file processing, configuration, searching, formatting, and person validation
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Errors returned by FileProcessor operations
+#[derive(Debug)]
+pub enum FileProcessorError {
+    NotFound(PathBuf),
+    PermissionDenied(PathBuf),
+    OutsideBasePath(PathBuf),
+    FileTooLarge { path: PathBuf, size: u64, limit: u64 },
+    ParentMissing(PathBuf),
+    NotADirectory(PathBuf),
+    InvalidLineRange { start: usize, end: usize },
+    InvalidSpan { start: usize, end: usize, len: usize },
+    NotCharBoundary(usize),
+    Decode { path: PathBuf, encoding: &'static str },
+    Encode { path: PathBuf, encoding: &'static str },
+    Io(io::Error),
+}
+
+/// Result alias for FileProcessor operations
+pub type Result<T> = std::result::Result<T, FileProcessorError>;
+
+impl FileProcessorError {
+    /// Classifies an IO error that occurred while accessing the given path
+    pub fn from_io<P: AsRef<Path>>(err: io::Error, path: P) -> Self {
+        match err.kind() {
+            io::ErrorKind::NotFound => FileProcessorError::NotFound(path.as_ref().to_path_buf()),
+            io::ErrorKind::PermissionDenied => {
+                FileProcessorError::PermissionDenied(path.as_ref().to_path_buf())
+            }
+            _ => FileProcessorError::Io(err),
+        }
+    }
+}
+
+impl fmt::Display for FileProcessorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileProcessorError::NotFound(path) => {
+                write!(f, "file not found: {}", path.display())
+            }
+            FileProcessorError::PermissionDenied(path) => {
+                write!(f, "permission denied: {}", path.display())
+            }
+            FileProcessorError::OutsideBasePath(path) => {
+                write!(f, "path is outside the base directory: {}", path.display())
+            }
+            FileProcessorError::FileTooLarge { path, size, limit } => write!(
+                f,
+                "file too large: {} is {} bytes, limit is {} bytes",
+                path.display(),
+                size,
+                limit
+            ),
+            FileProcessorError::ParentMissing(path) => {
+                write!(f, "parent directory does not exist: {}", path.display())
+            }
+            FileProcessorError::NotADirectory(path) => {
+                write!(f, "not a directory: {}", path.display())
+            }
+            FileProcessorError::InvalidLineRange { start, end } => {
+                write!(f, "invalid line range: {}..={} (lines are 1-based)", start, end)
+            }
+            FileProcessorError::InvalidSpan { start, end, len } => {
+                write!(f, "invalid span {}..{} for {} bytes of content", start, end, len)
+            }
+            FileProcessorError::NotCharBoundary(offset) => {
+                write!(f, "byte offset {} is inside a UTF-8 character", offset)
+            }
+            FileProcessorError::Decode { path, encoding } => {
+                write!(f, "{} is not valid {}", path.display(), encoding)
+            }
+            FileProcessorError::Encode { path, encoding } => {
+                write!(f, "{} has characters that can't be encoded as {}", path.display(), encoding)
+            }
+            FileProcessorError::Io(err) => write!(f, "io error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for FileProcessorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FileProcessorError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for FileProcessorError {
+    fn from(err: io::Error) -> Self {
+        FileProcessorError::Io(err)
+    }
+}
+
+/// Errors returned when reading a typed config file
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(FileProcessorError),
+    /// The file extension isn't a supported config format
+    UnsupportedFormat(PathBuf),
+    /// The content didn't match the expected structure, with the 1-based line if known
+    Parse { path: PathBuf, line: Option<usize>, message: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(err) => write!(f, "could not read config: {}", err),
+            ConfigError::UnsupportedFormat(path) => {
+                write!(f, "unsupported config format: {}", path.display())
+            }
+            ConfigError::Parse { path, line: Some(line), message } => {
+                write!(f, "invalid config {}:{}: {}", path.display(), line, message)
+            }
+            ConfigError::Parse { path, line: None, message } => {
+                write!(f, "invalid config {}: {}", path.display(), message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Read(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<FileProcessorError> for ConfigError {
+    fn from(err: FileProcessorError) -> Self {
+        ConfigError::Read(err)
+    }
+}
+
+/// Errors returned when searching a file with a regular expression
+#[derive(Debug)]
+pub enum GrepError {
+    /// The pattern isn't a valid regular expression
+    BadPattern { pattern: String, message: String },
+    Read(FileProcessorError),
+}
+
+impl fmt::Display for GrepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GrepError::BadPattern { pattern, message } => {
+                write!(f, "invalid pattern {:?}: {}", pattern, message)
+            }
+            GrepError::Read(err) => write!(f, "could not search file: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for GrepError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GrepError::Read(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<FileProcessorError> for GrepError {
+    fn from(err: FileProcessorError) -> Self {
+        GrepError::Read(err)
+    }
+}
+
+/// Errors returned when validating Person data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PersonError {
+    InvalidEmail(String),
+    InvalidAge(i64),
+}
+
+impl fmt::Display for PersonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersonError::InvalidEmail(email) => write!(f, "invalid email address: {}", email),
+            PersonError::InvalidAge(age) => write!(f, "invalid age: {}", age),
+        }
+    }
+}
+
+impl std::error::Error for PersonError {}
+
+/// Errors returned when command line arguments are malformed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgError {
+    MissingValue(String),
+    UnexpectedValue(String),
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgError::MissingValue(flag) => write!(f, "--{} expects a value", flag),
+            ArgError::UnexpectedValue(flag) => write!(f, "--{} does not take a value", flag),
+        }
+    }
+}
+
+impl std::error::Error for ArgError {}
+
+/// Errors returned when formatting source with an external tool
+#[derive(Debug)]
+pub enum FormatError {
+    ToolNotFound(String),
+    Failed { stderr: String },
+    Io(io::Error),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::ToolNotFound(tool) => write!(f, "{} is not installed or not in PATH", tool),
+            FormatError::Failed { stderr } => write!(f, "formatting failed: {}", stderr.trim()),
+            FormatError::Io(err) => write!(f, "io error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FormatError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for FormatError {
+    fn from(err: io::Error) -> Self {
+        FormatError::Io(err)
+    }
+}
@@ -1,25 +1,164 @@
 // Example Rust file demonstrating various language features
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{self, Read, Write};
-use std::path::Path;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
+
+pub mod analyzer;
+pub mod api;
+#[cfg(feature = "sha2")]
+pub mod changes;
+pub mod comments;
+pub mod complexity;
+pub mod diagnostics;
+pub mod diff;
+pub mod error;
+pub mod glob;
+pub mod imports;
+pub mod language;
+pub mod lexer;
+pub mod literals;
+pub mod markers;
+pub mod metrics;
+pub mod panics;
+pub mod position;
+pub mod refactor;
+#[cfg(feature = "serde")]
+pub mod report;
+#[cfg(feature = "regex")]
+pub mod secrets;
+pub mod syntax;
+pub mod text;
+pub mod transaction;
+pub mod whitespace;
+
+use diff::DiffLine;
+use error::{ArgError, FileProcessorError, FormatError, PersonError};
+use language::Language;
+use markers::MarkerHit;
+use metrics::LineCount;
+use text::{DetectedEncoding, Encoding, LineEnding};
+
+/// Age at which a person is considered an adult
+pub const ADULT_AGE: u32 = 18;
+
+/// Oldest age accepted by the validating constructors
+pub const MAX_AGE: u32 = 150;
+
+/// Number of leading bytes sampled when guessing whether a file is binary
+const BINARY_SAMPLE_SIZE: usize = 8 * 1024;
+
+/// Failed reads in a row after which `read_file_lossy` gives up on a file
+const MAX_READ_ERRORS: usize = 3;
+
+/// How long `watch` waits for changes to settle before reporting them
+#[cfg(feature = "notify")]
+const WATCH_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(200);
 
 /// Represents a person with basic information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Person {
     pub name: String,
     pub age: u32,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub email: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub greeting_template: Option<String>,
+}
+
+impl fmt::Display for Person {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.age)?;
+        if let Some(email) = &self.email {
+            write!(f, " <{}>", email)?;
+        }
+        Ok(())
+    }
 }
 
 /// Trait for greeting functionality
 pub trait Greeter {
     fn greet(&self) -> String;
+
+    /// Greets in the given locale, falling back to `greet` when unsupported
+    fn greet_in(&self, _locale: &str) -> String {
+        self.greet()
+    }
+
+    /// Greets the given person in this greeter's style, defaulting to the person's own greeting
+    fn greet_person(&self, person: &Person) -> String {
+        person.greet()
+    }
+}
+
+/// Greeting styles registered by name and chosen at runtime
+#[derive(Default)]
+pub struct GreeterRegistry {
+    greeters: HashMap<String, Box<dyn Greeter>>,
+}
+
+impl GreeterRegistry {
+    /// Creates an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a greeter under a style name, replacing any previous one
+    pub fn register(&mut self, name: &str, greeter: Box<dyn Greeter>) {
+        self.greeters.insert(name.to_string(), greeter);
+    }
+
+    /// Greets a person with the named style, or `None` if it isn't registered
+    pub fn greet_with(&self, name: &str, person: &Person) -> Option<String> {
+        self.greeters.get(name).map(|greeter| greeter.greet_person(person))
+    }
 }
 
 impl Greeter for Person {
     fn greet(&self) -> String {
-        format!("Hello, I'm {} and I'm {} years old", self.name, self.age)
+        match &self.greeting_template {
+            Some(template) => template
+                .replace("{name}", &self.name)
+                .replace("{age}", &self.age.to_string()),
+            None => self.greet_in("en"),
+        }
+    }
+
+    fn greet_in(&self, locale: &str) -> String {
+        // Match on the language part so "es-MX" or "fr_CA" still resolve
+        let language = locale
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        match language.as_str() {
+            "es" => format!("Hola, soy {} y tengo {} años", self.name, self.age),
+            "fr" => format!("Bonjour, je suis {} et j'ai {} ans", self.name, self.age),
+            _ => format!("Hello, I'm {} and I'm {} years old", self.name, self.age),
+        }
+    }
+}
+
+/// A group of people greeted together
+#[derive(Debug, Clone, Default)]
+pub struct Team(pub Vec<Person>);
+
+impl Greeter for Team {
+    /// Greets from every member, joining names with an Oxford comma
+    fn greet(&self) -> String {
+        let names: Vec<&str> = self.0.iter().map(|person| person.name.as_str()).collect();
+        match names.as_slice() {
+            [] => "Hello from the team".to_string(),
+            [only] => format!("Hello from {}", only),
+            [first, second] => format!("Hello from {} and {}", first, second),
+            [rest @ .., last] => format!("Hello from {}, and {}", rest.join(", "), last),
+        }
     }
 }
 
@@ -30,25 +169,157 @@ impl Person {
             name,
             age,
             email: None,
+            greeting_template: None,
+        }
+    }
+
+    /// Sets a custom greeting used by `greet`
+    ///
+    /// The template may contain `{name}` and `{age}` placeholders. Any other
+    /// `{...}` text is not a placeholder and is left in the greeting literally.
+    pub fn with_greeting_template(mut self, template: String) -> Self {
+        self.greeting_template = Some(template);
+        self
+    }
+
+    /// Creates a new Person after checking the age is plausible
+    pub fn try_new(name: String, age: u32) -> Result<Self, PersonError> {
+        if age > MAX_AGE {
+            return Err(PersonError::InvalidAge(age.into()));
+        }
+        Ok(Person::new(name, age))
+    }
+
+    /// Creates a new Person from a birth year relative to the current year
+    pub fn from_birth_year(name: String, birth_year: u32, current_year: u32) -> Result<Self, PersonError> {
+        let age = i64::from(current_year) - i64::from(birth_year);
+        match u32::try_from(age) {
+            Ok(age) => Person::try_new(name, age),
+            Err(_) => Err(PersonError::InvalidAge(age)),
         }
     }
 
+    /// Parses a Person from a JSON document
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> Result<Person, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
     /// Sets the email for the person
-    pub fn with_email(mut self, email: String) -> Self {
+    ///
+    /// Invalid addresses are ignored and leave the current email unchanged;
+    /// use `try_with_email` to find out whether the address was accepted.
+    pub fn with_email(self, email: String) -> Self {
+        let fallback = self.clone();
+        self.try_with_email(email).unwrap_or(fallback)
+    }
+
+    /// Sets the email for the person after validating its format
+    pub fn try_with_email(mut self, email: String) -> Result<Self, PersonError> {
+        let mut parts = email.split('@');
+        let valid = match (parts.next(), parts.next(), parts.next()) {
+            (Some(local), Some(domain), None) => !local.is_empty() && !domain.is_empty(),
+            _ => false,
+        };
+        if !valid {
+            return Err(PersonError::InvalidEmail(email));
+        }
         self.email = Some(email);
-        self
+        Ok(self)
     }
 
     /// Checks if the person is an adult
     pub fn is_adult(&self) -> bool {
-        self.age >= 18
+        self.age >= ADULT_AGE
+    }
+}
+
+/// Every FileProcessor setting, so processors can be configured from a file
+///
+/// Missing fields take the same defaults as `FileProcessor::new`, so a JSON
+/// or TOML config only needs the settings it changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct FileProcessorConfig {
+    pub cache: bool,
+    pub max_file_size: Option<u64>,
+    pub dedup_processed: bool,
+    pub line_ending: LineEnding,
+    pub convert_tabs: Option<usize>,
+    pub backup: bool,
+    pub dry_run: bool,
+    pub create_parents: bool,
+    pub lossy_decoding: bool,
+    pub atomic_writes: bool,
+    pub write_attempts: u32,
+    pub retry_delay_ms: u64,
+    pub ignore_patterns: Vec<String>,
+    pub watch_pattern: Option<String>,
+    pub max_depth: Option<usize>,
+    pub follow_symlinks: bool,
+    #[cfg(feature = "fs2")]
+    pub locking: bool,
+}
+
+impl Default for FileProcessorConfig {
+    fn default() -> Self {
+        FileProcessorConfig {
+            cache: false,
+            max_file_size: None,
+            dedup_processed: false,
+            line_ending: LineEnding::Preserve,
+            convert_tabs: None,
+            backup: false,
+            dry_run: false,
+            create_parents: false,
+            lossy_decoding: false,
+            atomic_writes: false,
+            write_attempts: 1,
+            retry_delay_ms: 0,
+            ignore_patterns: Vec::new(),
+            watch_pattern: None,
+            max_depth: None,
+            follow_symlinks: false,
+            #[cfg(feature = "fs2")]
+            locking: false,
+        }
     }
 }
 
+/// Callback receiving `(files_done, files_total)` during directory scans
+pub type ProgressCallback = Box<dyn FnMut(usize, usize) + Send + Sync>;
+
 /// File processor for handling file operations
 pub struct FileProcessor {
     base_path: String,
     processed_files: Vec<String>,
+    cache: Option<HashMap<PathBuf, (SystemTime, String)>>,
+    max_file_size: Option<u64>,
+    bytes_read: u64,
+    bytes_written: u64,
+    dedup_processed: bool,
+    line_ending: LineEnding,
+    convert_tabs: Option<usize>,
+    backup: bool,
+    dry_run: bool,
+    planned_writes: Vec<(String, usize)>,
+    planned_diffs: Vec<(String, Vec<DiffLine>)>,
+    planned_deletes: Vec<String>,
+    create_parents: bool,
+    lossy_decoding: bool,
+    write_attempts: u32,
+    retry_delay: Duration,
+    atomic_writes: bool,
+    skipped_files: Vec<String>,
+    deleted_files: Vec<String>,
+    by_language: HashMap<String, usize>,
+    ignore_patterns: Vec<String>,
+    watch_pattern: Option<String>,
+    max_depth: Option<usize>,
+    follow_symlinks: bool,
+    on_progress: Option<ProgressCallback>,
+    #[cfg(feature = "fs2")]
+    locking: bool,
 }
 
 impl FileProcessor {
@@ -57,128 +328,3856 @@ impl FileProcessor {
         FileProcessor {
             base_path: base_path.as_ref().to_string_lossy().to_string(),
             processed_files: Vec::new(),
+            cache: None,
+            max_file_size: None,
+            bytes_read: 0,
+            bytes_written: 0,
+            dedup_processed: false,
+            line_ending: LineEnding::Preserve,
+            convert_tabs: None,
+            backup: false,
+            dry_run: false,
+            planned_writes: Vec::new(),
+            planned_diffs: Vec::new(),
+            planned_deletes: Vec::new(),
+            create_parents: false,
+            lossy_decoding: false,
+            write_attempts: 1,
+            retry_delay: Duration::ZERO,
+            atomic_writes: false,
+            skipped_files: Vec::new(),
+            deleted_files: Vec::new(),
+            by_language: HashMap::new(),
+            ignore_patterns: Vec::new(),
+            watch_pattern: None,
+            max_depth: None,
+            follow_symlinks: false,
+            on_progress: None,
+            #[cfg(feature = "fs2")]
+            locking: false,
         }
     }
 
-    /// Reads content from a file
-    pub fn read_file<P: AsRef<Path>>(&mut self, filename: P) -> io::Result<String> {
-        let file_path = Path::new(&self.base_path).join(filename.as_ref());
-        let mut file = File::open(&file_path)?;
-        let mut content = String::new();
-        file.read_to_string(&mut content)?;
-        
-        self.processed_files.push(
-            filename.as_ref().to_string_lossy().to_string()
-        );
-        
-        Ok(content)
+    /// Starts buffering writes and deletes to apply all-or-nothing
+    pub fn begin(&mut self) -> transaction::Transaction<'_> {
+        transaction::Transaction::new(self)
     }
 
-    /// Writes content to a file
-    pub fn write_file<P: AsRef<Path>>(&mut self, filename: P, content: &str) -> io::Result<()> {
-        let file_path = Path::new(&self.base_path).join(filename.as_ref());
-        let mut file = File::create(&file_path)?;
-        file.write_all(content.as_bytes())?;
-        
-        self.processed_files.push(
-            filename.as_ref().to_string_lossy().to_string()
-        );
-        
+    /// Creates a FileProcessor with every setting taken from a config
+    pub fn from_config<P: AsRef<Path>>(base_path: P, config: FileProcessorConfig) -> Self {
+        let mut processor = FileProcessor::new(base_path)
+            .with_dedup_processed(config.dedup_processed)
+            .with_line_ending(config.line_ending)
+            .with_backup(config.backup)
+            .with_dry_run(config.dry_run)
+            .with_create_parents(config.create_parents)
+            .with_lossy_decoding(config.lossy_decoding)
+            .with_atomic_writes(config.atomic_writes)
+            .with_retries(config.write_attempts, Duration::from_millis(config.retry_delay_ms))
+            .with_ignore_patterns(config.ignore_patterns)
+            .with_follow_symlinks(config.follow_symlinks);
+        #[cfg(feature = "fs2")]
+        {
+            processor = processor.with_locking(config.locking);
+        }
+        processor.max_file_size = config.max_file_size;
+        processor.convert_tabs = config.convert_tabs;
+        processor.watch_pattern = config.watch_pattern;
+        processor.max_depth = config.max_depth;
+        if config.cache {
+            processor.enable_cache();
+        }
+        processor
+    }
+
+    /// Points the processor at a different base directory, keeping its state
+    ///
+    /// Fails if the path doesn't exist or isn't a directory, leaving the
+    /// current base path in place.
+    pub fn set_base_path<P: AsRef<Path>>(&mut self, base: P) -> error::Result<()> {
+        let base = base.as_ref();
+        let metadata = fs::metadata(base).map_err(|e| FileProcessorError::from_io(e, base))?;
+        if !metadata.is_dir() {
+            return Err(FileProcessorError::NotADirectory(base.to_path_buf()));
+        }
+        self.base_path = base.to_string_lossy().to_string();
         Ok(())
     }
 
-    /// Gets processing statistics
-    pub fn get_stats(&self) -> HashMap<String, serde_json::Value> {
-        let mut stats = HashMap::new();
-        stats.insert("base_path".to_string(), 
-                    serde_json::Value::String(self.base_path.clone()));
-        stats.insert("processed_files_count".to_string(), 
-                    serde_json::Value::Number(self.processed_files.len().into()));
-        stats.insert("files".to_string(), 
-                    serde_json::Value::Array(
-                        self.processed_files.iter()
-                            .map(|f| serde_json::Value::String(f.clone()))
-                            .collect()
-                    ));
-        stats
+    /// Skips paths matching gitignore-style globs during directory processing
+    ///
+    /// Patterns are matched against paths relative to the base path, so
+    /// `target/` excludes every `target` directory and `*.min.js` excludes
+    /// minified files at any depth.
+    pub fn with_ignore_patterns(mut self, patterns: Vec<String>) -> Self {
+        self.ignore_patterns = patterns;
+        self
     }
-}
 
-/// Processes command line arguments
-pub fn process_arguments(args: Vec<String>) -> Vec<String> {
-    args.into_iter()
-        .skip(1) // Skip program name
-        .filter(|arg| !arg.trim().is_empty())
-        .map(|arg| arg.to_uppercase())
-        .collect()
-}
+    /// Limits how far directory processing descends below the starting directory
+    ///
+    /// Depth 0 processes only files directly inside it, depth 1 also their
+    /// immediate subdirectories, and so on.
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
 
-/// Main function demonstrating the module functionality
-fn main() -> io::Result<()> {
-    // Create a person
-    let person = Person::new("Alice Smith".to_string(), 25)
-        .with_email("alice@example.com".to_string());
-    
-    println!("{}", person.greet());
-    println!("Is adult: {}", person.is_adult());
+    /// Follows symlinks during directory processing instead of skipping them
+    ///
+    /// Links that lead outside the base path or back into a directory already
+    /// walked are skipped, so cycles can't recurse forever.
+    pub fn with_follow_symlinks(mut self, enabled: bool) -> Self {
+        self.follow_symlinks = enabled;
+        self
+    }
 
-    // File processing example
-    let mut processor = FileProcessor::new(".");
-    
-    // Process command line arguments
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() > 1 {
-        let processed_args = process_arguments(args);
-        println!("Processed arguments: {:?}", processed_args);
+    /// Reports progress through `process_dir` and `analyze_dir`
+    ///
+    /// The callback is invoked with `(files_done, files_total)` after each
+    /// file, skipped binaries included, once the directory has been listed.
+    pub fn with_progress<F: FnMut(usize, usize) + Send + Sync + 'static>(mut self, on_progress: F) -> Self {
+        self.on_progress = Some(Box::new(on_progress));
+        self
     }
 
-    // Display stats
-    let stats = processor.get_stats();
-    println!("Processor stats: {}", serde_json::to_string_pretty(&stats)?);
+    fn report_progress(&mut self, done: usize, total: usize) {
+        if let Some(on_progress) = self.on_progress.as_mut() {
+            on_progress(done, total);
+        }
+    }
 
-    Ok(())
-}
+    fn is_ignored(&self, relative: &Path, is_dir: bool) -> bool {
+        let path = slash_path(relative);
+        self.ignore_patterns
+            .iter()
+            .any(|pattern| glob::ignore_match(pattern, &path, is_dir))
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Holds an advisory lock while writing so concurrent writers don't interleave
+    ///
+    /// The lock is taken on a hidden `.<name>.lock` file next to the target,
+    /// which is left in place afterwards.
+    #[cfg(feature = "fs2")]
+    pub fn with_locking(mut self, enabled: bool) -> Self {
+        self.locking = enabled;
+        self
+    }
 
-    #[test]
-    fn test_person_creation() {
-        let person = Person::new("John Doe".to_string(), 30);
-        assert_eq!(person.name, "John Doe");
-        assert_eq!(person.age, 30);
-        assert!(person.email.is_none());
+    /// Acquires the write lock for a file if locking is enabled, released on drop
+    #[cfg(feature = "fs2")]
+    fn lock_for_write(&self, file_path: &Path) -> error::Result<Option<File>> {
+        use fs2::FileExt;
+
+        if !self.locking {
+            return Ok(None);
+        }
+        let name = file_path.file_name().unwrap_or_default().to_string_lossy();
+        let lock_path = file_path.with_file_name(format!(".{}.lock", name));
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .map_err(|e| FileProcessorError::from_io(e, &lock_path))?;
+        lock.lock_exclusive()
+            .map_err(|e| FileProcessorError::from_io(e, &lock_path))?;
+        Ok(Some(lock))
     }
 
-    #[test]
-    fn test_person_with_email() {
-        let person = Person::new("Jane Doe".to_string(), 25)
-            .with_email("jane@example.com".to_string());
-        assert!(person.email.is_some());
-        assert_eq!(person.email.unwrap(), "jane@example.com");
+    #[cfg(not(feature = "fs2"))]
+    fn lock_for_write(&self, _file_path: &Path) -> error::Result<Option<File>> {
+        Ok(None)
     }
 
-    #[test]
-    fn test_is_adult() {
-        let adult = Person::new("Adult".to_string(), 18);
-        let minor = Person::new("Minor".to_string(), 17);
-        
-        assert!(adult.is_adult());
-        assert!(!minor.is_adult());
+    /// Retries `write_file`, `write_file_atomic`, and `write_bytes` on transient IO errors
+    ///
+    /// Writes are attempted up to `count` times in total, waiting `base_delay`
+    /// before the first retry and doubling it each time after. Only
+    /// `Interrupted`, `WouldBlock`, and `TimedOut` errors are retried; others
+    /// fail immediately.
+    pub fn with_retries(mut self, count: u32, base_delay: Duration) -> Self {
+        self.write_attempts = count.max(1);
+        self.retry_delay = base_delay;
+        self
     }
 
-    #[test]
-    fn test_process_arguments() {
-        let args = vec![
-            "program_name".to_string(),
-            "hello".to_string(),
-            "world".to_string(),
-            "".to_string(),
-            "rust".to_string(),
-        ];
-        
-        let processed = process_arguments(args);
-        assert_eq!(processed, vec!["HELLO", "WORLD", "RUST"]);
+    /// Runs an IO operation, retrying transient failures with exponential backoff
+    fn retry<T>(&self, mut operation: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+        let mut delay = self.retry_delay;
+        let mut attempt = 1;
+        loop {
+            match operation() {
+                Err(err) if attempt < self.write_attempts && is_transient(&err) => {
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Writes edits made by `apply_edit` through `write_file_atomic`
+    pub fn with_atomic_writes(mut self, enabled: bool) -> Self {
+        self.atomic_writes = enabled;
+        self
+    }
+
+    /// Replaces malformed sequences with U+FFFD instead of failing to decode
+    pub fn with_lossy_decoding(mut self, enabled: bool) -> Self {
+        self.lossy_decoding = enabled;
+        self
+    }
+
+    /// Creates missing parent directories when writing nested paths
+    pub fn with_create_parents(mut self, enabled: bool) -> Self {
+        self.create_parents = enabled;
+        self
+    }
+
+    /// Ensures the parent directory of a resolved path exists before writing
+    fn prepare_parent(&self, file_path: &Path) -> error::Result<()> {
+        let Some(parent) = file_path.parent() else {
+            return Ok(());
+        };
+        if parent.is_dir() {
+            return Ok(());
+        }
+        if !self.create_parents {
+            return Err(FileProcessorError::ParentMissing(parent.to_path_buf()));
+        }
+        fs::create_dir_all(parent).map_err(|e| FileProcessorError::from_io(e, parent))
+    }
+
+    /// Records writes in `planned_writes` and `planned_diffs`, and deletes in
+    /// `planned_deletes`, instead of touching disk
+    pub fn with_dry_run(mut self, enabled: bool) -> Self {
+        self.dry_run = enabled;
+        self
+    }
+
+    /// Gets the writes skipped in dry-run mode as (path, content length) pairs
+    pub fn planned_writes(&self) -> &[(String, usize)] {
+        &self.planned_writes
+    }
+
+    /// Gets the writes skipped in dry-run mode as diffs from the current content
+    ///
+    /// Files that don't exist yet diff against empty content, and appends
+    /// show the appended lines. Entries line up with `planned_writes`.
+    pub fn planned_diffs(&self) -> Vec<(String, Vec<DiffLine>)> {
+        self.planned_diffs.clone()
+    }
+
+    /// Records a write skipped in dry-run mode along with its diff
+    fn plan_write(&mut self, filename: &Path, file_path: &Path, len: usize, content: &str) {
+        let name = filename.to_string_lossy().to_string();
+        let current = fs::read(file_path)
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .unwrap_or_default();
+        self.planned_diffs.push((name.clone(), diff::diff_lines(&current, content)));
+        self.planned_writes.push((name, len));
+    }
+
+    /// Records copying a file onto `to` as a planned write, returning the bytes it would copy
+    fn plan_copy(&mut self, to: &Path, from_path: &Path, to_path: &Path) -> error::Result<u64> {
+        let bytes = fs::read(from_path).map_err(|e| FileProcessorError::from_io(e, from_path))?;
+        self.plan_write(to, to_path, bytes.len(), &String::from_utf8_lossy(&bytes));
+        Ok(bytes.len() as u64)
+    }
+
+    /// Gets the files that deletes and moves would have removed in dry-run mode
+    pub fn planned_deletes(&self) -> &[String] {
+        &self.planned_deletes
+    }
+
+    /// Copies existing files to a `.bak` sibling before they are overwritten
+    pub fn with_backup(mut self, enabled: bool) -> Self {
+        self.backup = enabled;
+        self
+    }
+
+    /// Backs up an existing file before a write replaces it
+    ///
+    /// Uses `<name>.bak`, or `<name>.<unix seconds>.bak` when that is taken,
+    /// and records the backup as processed.
+    fn backup_existing(&mut self, filename: &Path, file_path: &Path) -> error::Result<()> {
+        if !self.backup || !file_path.is_file() {
+            return Ok(());
+        }
+
+        let name = file_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut backup_name = format!("{}.bak", name);
+        if file_path.with_file_name(&backup_name).exists() {
+            let seconds = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            backup_name = format!("{}.{}.bak", name, seconds);
+            let mut suffix = 1;
+            while file_path.with_file_name(&backup_name).exists() {
+                backup_name = format!("{}.{}-{}.bak", name, seconds, suffix);
+                suffix += 1;
+            }
+        }
+
+        let backup_path = file_path.with_file_name(&backup_name);
+        fs::copy(file_path, &backup_path)
+            .map_err(|e| FileProcessorError::from_io(e, &backup_path))?;
+        self.record_file(&filename.with_file_name(&backup_name));
+        Ok(())
+    }
+
+    /// Sets the line ending that text writes are normalized to
+    pub fn with_line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Expands tabs in leading indentation to `width` spaces when writing text
+    pub fn with_convert_tabs(mut self, width: usize) -> Self {
+        self.convert_tabs = Some(width);
+        self
+    }
+
+    /// Records each processed file only the first time it is seen, keeping first-seen order
+    pub fn with_dedup_processed(mut self, enabled: bool) -> Self {
+        self.dedup_processed = enabled;
+        self
+    }
+
+    /// Sets the maximum size in bytes of files that may be read
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Rejects files larger than the configured maximum size before reading them
+    fn check_file_size(&self, file_path: &Path) -> error::Result<()> {
+        if let Some(limit) = self.max_file_size {
+            let size = fs::metadata(file_path)
+                .map_err(|e| FileProcessorError::from_io(e, file_path))?
+                .len();
+            if size > limit {
+                return Err(FileProcessorError::FileTooLarge {
+                    path: file_path.to_path_buf(),
+                    size,
+                    limit,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Enables caching of read_file results keyed by path and modification time
+    pub fn enable_cache(&mut self) {
+        if self.cache.is_none() {
+            self.cache = Some(HashMap::new());
+        }
+    }
+
+    /// Drops all cached file contents, leaving caching enabled
+    pub fn clear_cache(&mut self) {
+        if let Some(cache) = &mut self.cache {
+            cache.clear();
+        }
+    }
+
+    /// Removes a resolved path from the cache after it has been written
+    fn invalidate_cache(&mut self, file_path: &Path) {
+        if let Some(cache) = &mut self.cache {
+            cache.remove(file_path);
+        }
+    }
+
+    /// Resolves a filename against the base path, rejecting paths that escape it
+    fn resolve_path<P: AsRef<Path>>(&self, filename: P) -> error::Result<PathBuf> {
+        let filename = filename.as_ref();
+        let outside = || FileProcessorError::OutsideBasePath(filename.to_path_buf());
+
+        let mut relative = PathBuf::new();
+        for component in filename.components() {
+            match component {
+                Component::Normal(part) => relative.push(part),
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    if !relative.pop() {
+                        return Err(outside());
+                    }
+                }
+                Component::RootDir | Component::Prefix(_) => return Err(outside()),
+            }
+        }
+
+        let base = fs::canonicalize(&self.base_path)
+            .map_err(|e| FileProcessorError::from_io(e, &self.base_path))?;
+        let joined = base.join(&relative);
+
+        // Canonicalize the deepest existing ancestor so symlinks can't lead outside the base
+        let existing = joined
+            .ancestors()
+            .find(|p| fs::symlink_metadata(p).is_ok())
+            .unwrap_or(&base);
+        let remainder = joined.strip_prefix(existing).unwrap_or(Path::new(""));
+        let mut resolved = fs::canonicalize(existing)
+            .map_err(|e| FileProcessorError::from_io(e, existing))?;
+        if !remainder.as_os_str().is_empty() {
+            resolved.push(remainder);
+        }
+
+        if !resolved.starts_with(&base) {
+            return Err(outside());
+        }
+        Ok(resolved)
+    }
+
+    /// Gets a path relative to the base path for display, or `None` if it's outside
+    ///
+    /// Relative paths are resolved against the base path as for reads, so `..`
+    /// and symlinks can't escape it. Absolute paths are canonicalized when
+    /// they exist and must then lie under the base path; paths that don't
+    /// exist are resolved from the part after the base path, so a `..` that
+    /// climbs out of it gives `None`.
+    pub fn relative_path<P: AsRef<Path>>(&self, filename: P) -> Option<PathBuf> {
+        let filename = filename.as_ref();
+        let base = fs::canonicalize(&self.base_path).ok()?;
+        let resolved = if filename.is_absolute() {
+            match fs::canonicalize(filename) {
+                Ok(resolved) => resolved,
+                Err(_) => {
+                    let rest = filename
+                        .strip_prefix(&self.base_path)
+                        .or_else(|_| filename.strip_prefix(&base))
+                        .ok()?;
+                    self.resolve_path(rest).ok()?
+                }
+            }
+        } else {
+            self.resolve_path(filename).ok()?
+        };
+        resolved.strip_prefix(&base).ok().map(Path::to_path_buf)
+    }
+
+    /// Gets the deepest directory containing every processed file
+    ///
+    /// Returns `None` when nothing has been processed or the files share no
+    /// directory below the base path, such as `README.md` and `src/lib.rs`.
+    pub fn common_prefix(&self) -> Option<PathBuf> {
+        let mut files = self.processed_files.iter();
+        let first = files.next()?;
+        let mut prefix: Vec<&str> = first.split('/').collect();
+        prefix.pop();
+        for file in files {
+            let dirs = file.split('/').collect::<Vec<_>>();
+            let dirs = &dirs[..dirs.len() - 1];
+            let shared = prefix.iter().zip(dirs).take_while(|(a, b)| a == b).count();
+            prefix.truncate(shared);
+        }
+        (!prefix.is_empty()).then(|| prefix.iter().collect())
+    }
+
+    /// Checks whether a path exists under the base path
+    ///
+    /// Paths that escape the base path are reported as missing rather than
+    /// as an error, as are `is_file` and `is_dir`.
+    pub fn exists<P: AsRef<Path>>(&self, filename: P) -> bool {
+        self.resolve_path(filename).is_ok_and(|path| path.exists())
+    }
+
+    /// Checks whether a path under the base path is a regular file
+    pub fn is_file<P: AsRef<Path>>(&self, filename: P) -> bool {
+        self.resolve_path(filename).is_ok_and(|path| path.is_file())
+    }
+
+    /// Checks whether a path under the base path is a directory
+    pub fn is_dir<P: AsRef<Path>>(&self, filename: P) -> bool {
+        self.resolve_path(filename).is_ok_and(|path| path.is_dir())
+    }
+
+    /// Reads content from a file, serving it from the cache when unchanged on disk
+    pub fn read_file<P: AsRef<Path>>(&mut self, filename: P) -> error::Result<String> {
+        let file_path = self.resolve_path(filename.as_ref())?;
+        let modified = match self.cache {
+            Some(_) => Some(
+                fs::metadata(&file_path)
+                    .and_then(|m| m.modified())
+                    .map_err(|e| FileProcessorError::from_io(e, &file_path))?,
+            ),
+            None => None,
+        };
+        if let (Some(cache), Some(modified)) = (&self.cache, modified) {
+            if let Some((cached_at, content)) = cache.get(&file_path) {
+                if *cached_at == modified {
+                    let content = content.clone();
+                    self.record_file(filename.as_ref());
+                    return Ok(content);
+                }
+            }
+        }
+
+        let content = self.read_text(&file_path)?;
+
+        if let (Some(cache), Some(modified)) = (&mut self.cache, modified) {
+            cache.insert(file_path, (modified, content.clone()));
+        }
+        self.bytes_read += content.len() as u64;
+        self.record_file(filename.as_ref());
+
+        Ok(content)
+    }
+
+    /// Reads a resolved path as text after checking the size limit
+    fn read_text(&self, file_path: &Path) -> error::Result<String> {
+        self.check_file_size(file_path)?;
+        let mut file = File::open(file_path)
+            .map_err(|e| FileProcessorError::from_io(e, file_path))?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        Ok(content)
+    }
+
+    /// Reads as much of a file as possible, collecting errors instead of failing
+    ///
+    /// The file is read in chunks; a failed chunk is recorded and the read
+    /// continues, giving up after `MAX_READ_ERRORS` failures in a row. Invalid
+    /// UTF-8 becomes U+FFFD, and the size limit and cache don't apply. The file
+    /// is recorded as processed whenever its path is under the base path, even
+    /// if it couldn't be opened, so bulk runs can report on it.
+    pub fn read_file_lossy<P: AsRef<Path>>(&mut self, filename: P) -> (String, Vec<FileProcessorError>) {
+        let file_path = match self.resolve_path(filename.as_ref()) {
+            Ok(path) => path,
+            Err(err) => return (String::new(), vec![err]),
+        };
+        let mut errors = Vec::new();
+        let bytes = match File::open(&file_path) {
+            Ok(file) => read_chunks(file, &mut errors),
+            Err(err) => {
+                errors.push(FileProcessorError::from_io(err, &file_path));
+                Vec::new()
+            }
+        };
+        self.bytes_read += bytes.len() as u64;
+        self.record_file(filename.as_ref());
+        (String::from_utf8_lossy(&bytes).into_owned(), errors)
+    }
+
+    /// Reads many files concurrently, returning results in input order
+    ///
+    /// Files are read on the rayon pool through `&self`; processed files and
+    /// byte counters are then updated on the calling thread, so tracking
+    /// stays in input order and nothing is shared mutably between threads.
+    #[cfg(feature = "rayon")]
+    pub fn read_files_parallel<P: AsRef<Path> + Sync>(
+        &mut self,
+        files: Vec<P>,
+    ) -> Vec<(String, error::Result<String>)> {
+        use rayon::prelude::*;
+
+        let processor = &*self;
+        let results: Vec<(String, error::Result<String>)> = files
+            .par_iter()
+            .map(|filename| {
+                let content = processor
+                    .resolve_path(filename.as_ref())
+                    .and_then(|file_path| processor.read_text(&file_path));
+                (filename.as_ref().to_string_lossy().to_string(), content)
+            })
+            .collect();
+
+        for (filename, content) in &results {
+            if let Ok(content) = content {
+                self.bytes_read += content.len() as u64;
+                self.record_file(Path::new(filename));
+            }
+        }
+        results
+    }
+
+    /// Reads a file line by line without loading it into memory
+    ///
+    /// Only one buffered line is held at a time, so this suits large logs where
+    /// `read_file` would allocate the whole file. The file is recorded as
+    /// processed once it has been opened.
+    pub fn read_file_lines<P: AsRef<Path>>(
+        &mut self,
+        filename: P,
+    ) -> error::Result<impl Iterator<Item = io::Result<String>>> {
+        let file_path = self.resolve_path(filename.as_ref())?;
+        let file = File::open(&file_path)
+            .map_err(|e| FileProcessorError::from_io(e, &file_path))?;
+
+        self.record_file(filename.as_ref());
+
+        Ok(BufReader::new(file).lines())
+    }
+
+    /// Reads lines `start..=end` (1-based) without loading the whole file
+    ///
+    /// `end` is clamped to the last line, so the result may be shorter than
+    /// requested or empty if `start` is past the end of the file.
+    pub fn read_line_range<P: AsRef<Path>>(
+        &mut self,
+        filename: P,
+        start: usize,
+        end: usize,
+    ) -> error::Result<Vec<String>> {
+        if start == 0 || start > end {
+            return Err(FileProcessorError::InvalidLineRange { start, end });
+        }
+        let file_path = self.resolve_path(filename.as_ref())?;
+        let lines = self
+            .read_file_lines(filename)?
+            .skip(start - 1)
+            .take(end - start + 1)
+            .collect::<io::Result<Vec<_>>>()
+            .map_err(|e| FileProcessorError::from_io(e, &file_path))?;
+        Ok(lines)
+    }
+
+    /// Applies tab conversion and line ending normalization to text being written
+    fn prepare_content<'a>(&self, content: &'a str) -> Cow<'a, str> {
+        let content = match self.convert_tabs {
+            Some(width) => text::expand_leading_tabs(content, width),
+            None => Cow::Borrowed(content),
+        };
+        match self.line_ending.apply(&content) {
+            Cow::Borrowed(_) => content,
+            Cow::Owned(normalized) => Cow::Owned(normalized),
+        }
+    }
+
+    /// Writes content to a file
+    pub fn write_file<P: AsRef<Path>>(&mut self, filename: P, content: &str) -> error::Result<()> {
+        let content = self.prepare_content(content);
+        let file_path = self.resolve_path(filename.as_ref())?;
+        if self.dry_run {
+            self.plan_write(filename.as_ref(), &file_path, content.len(), &content);
+            return Ok(());
+        }
+        self.prepare_parent(&file_path)?;
+        let _lock = self.lock_for_write(&file_path)?;
+        self.backup_existing(filename.as_ref(), &file_path)?;
+        self.retry(|| File::create(&file_path)?.write_all(content.as_bytes()))
+            .map_err(|e| FileProcessorError::from_io(e, &file_path))?;
+
+        self.bytes_written += content.len() as u64;
+        self.invalidate_cache(&file_path);
+        self.record_file(filename.as_ref());
+
+        Ok(())
+    }
+
+    /// Writes content to a file and sets its Unix permission bits, such as `0o755`
+    ///
+    /// On other platforms the mode is ignored and this behaves like `write_file`.
+    pub fn write_file_with_mode<P: AsRef<Path>>(&mut self, filename: P, content: &str, mode: u32) -> error::Result<()> {
+        self.write_file(filename.as_ref(), content)?;
+        if self.dry_run {
+            return Ok(());
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let file_path = self.resolve_path(filename.as_ref())?;
+            fs::set_permissions(&file_path, fs::Permissions::from_mode(mode))
+                .map_err(|e| FileProcessorError::from_io(e, &file_path))?;
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+
+        Ok(())
+    }
+
+    /// Writes content to a file atomically via a temp file and rename
+    ///
+    /// Readers see either the old content or the new content, never a partial
+    /// write, as long as the temp file and target share a filesystem.
+    pub fn write_file_atomic<P: AsRef<Path>>(&mut self, filename: P, content: &str) -> error::Result<()> {
+        let content = self.prepare_content(content);
+        let file_path = self.resolve_path(filename.as_ref())?;
+        if self.dry_run {
+            self.plan_write(filename.as_ref(), &file_path, content.len(), &content);
+            return Ok(());
+        }
+        self.prepare_parent(&file_path)?;
+        let _lock = self.lock_for_write(&file_path)?;
+        self.backup_existing(filename.as_ref(), &file_path)?;
+        let temp_path = temp_sibling(&file_path);
+        let result = self.retry(|| {
+            let mut file = File::create(&temp_path)?;
+            file.write_all(content.as_bytes())?;
+            file.sync_all()?;
+            fs::rename(&temp_path, &file_path)
+        });
+        if let Err(err) = result {
+            let _ = fs::remove_file(&temp_path);
+            return Err(FileProcessorError::from_io(err, &file_path));
+        }
+
+        self.bytes_written += content.len() as u64;
+        self.invalidate_cache(&file_path);
+        self.record_file(filename.as_ref());
+
+        Ok(())
+    }
+
+    /// Replaces the byte span `start..end` of a file's content and writes it back
+    ///
+    /// Both offsets must lie within the content and on UTF-8 character
+    /// boundaries. The write is atomic when `with_atomic_writes` is enabled.
+    pub fn apply_edit<P: AsRef<Path>>(
+        &mut self,
+        filename: P,
+        start: usize,
+        end: usize,
+        replacement: &str,
+    ) -> error::Result<()> {
+        let file_path = self.resolve_path(filename.as_ref())?;
+        let mut content = self.read_text(&file_path)?;
+        self.bytes_read += content.len() as u64;
+
+        if start > end || end > content.len() {
+            return Err(FileProcessorError::InvalidSpan { start, end, len: content.len() });
+        }
+        if let Some(&offset) = [start, end].iter().find(|&&i| !content.is_char_boundary(i)) {
+            return Err(FileProcessorError::NotCharBoundary(offset));
+        }
+        content.replace_range(start..end, replacement);
+
+        if self.atomic_writes {
+            self.write_file_atomic(filename, &content)
+        } else {
+            self.write_file(filename, &content)
+        }
+    }
+
+    /// Empties a file, creating it if absent
+    pub fn truncate_file<P: AsRef<Path>>(&mut self, filename: P) -> error::Result<()> {
+        let file_path = self.resolve_path(filename.as_ref())?;
+        if self.dry_run {
+            self.plan_write(filename.as_ref(), &file_path, 0, "");
+            return Ok(());
+        }
+        self.prepare_parent(&file_path)?;
+        let _lock = self.lock_for_write(&file_path)?;
+        self.backup_existing(filename.as_ref(), &file_path)?;
+        File::create(&file_path).map_err(|e| FileProcessorError::from_io(e, &file_path))?;
+
+        self.invalidate_cache(&file_path);
+        self.record_file(filename.as_ref());
+
+        Ok(())
+    }
+
+    /// Deletes a file, recording it in `deleted_files`
+    ///
+    /// In dry-run mode nothing is deleted and the file is recorded in
+    /// `planned_deletes` instead. With backups enabled, the file is copied
+    /// aside first.
+    pub fn delete_file<P: AsRef<Path>>(&mut self, filename: P) -> error::Result<()> {
+        let file_path = self.resolve_path(filename.as_ref())?;
+        if !file_path.is_file() {
+            return Err(FileProcessorError::NotFound(file_path));
+        }
+        if self.dry_run {
+            self.planned_deletes.push(slash_path(filename.as_ref()));
+            return Ok(());
+        }
+        let _lock = self.lock_for_write(&file_path)?;
+        self.backup_existing(filename.as_ref(), &file_path)?;
+        fs::remove_file(&file_path).map_err(|e| FileProcessorError::from_io(e, &file_path))?;
+
+        self.invalidate_cache(&file_path);
+        self.deleted_files.push(slash_path(filename.as_ref()));
+
+        Ok(())
+    }
+
+    /// Gets the files removed by `delete_file`
+    pub fn deleted_files(&self) -> &[String] {
+        &self.deleted_files
+    }
+
+    /// Appends content to a file, creating it if absent
+    pub fn append_file<P: AsRef<Path>>(&mut self, filename: P, content: &str) -> error::Result<()> {
+        let content = self.prepare_content(content);
+        let file_path = self.resolve_path(filename.as_ref())?;
+        if self.dry_run {
+            let mut appended = fs::read_to_string(&file_path).unwrap_or_default();
+            appended.push_str(&content);
+            self.plan_write(filename.as_ref(), &file_path, content.len(), &appended);
+            return Ok(());
+        }
+        self.prepare_parent(&file_path)?;
+        let _lock = self.lock_for_write(&file_path)?;
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&file_path)
+            .map_err(|e| FileProcessorError::from_io(e, &file_path))?;
+        file.write_all(content.as_bytes())?;
+
+        self.bytes_written += content.len() as u64;
+        self.invalidate_cache(&file_path);
+        self.record_file(filename.as_ref());
+
+        Ok(())
+    }
+
+    /// Reads a config file and deserializes it based on its extension
+    ///
+    /// `.json` files are parsed with serde_json and, with the `toml` feature,
+    /// `.toml` files with toml. Parse errors carry the file path and the line
+    /// of the problem when the parser reports one.
+    #[cfg(feature = "serde")]
+    pub fn read_config<T: serde::de::DeserializeOwned, P: AsRef<Path>>(
+        &mut self,
+        filename: P,
+    ) -> Result<T, error::ConfigError> {
+        let path = filename.as_ref();
+        let parse_error = |line, message| error::ConfigError::Parse {
+            path: path.to_path_buf(),
+            line,
+            message,
+        };
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => {
+                let content = self.read_file(path)?;
+                serde_json::from_str(&content).map_err(|e| parse_error(Some(e.line()), e.to_string()))
+            }
+            #[cfg(feature = "toml")]
+            Some("toml") => {
+                let content = self.read_file(path)?;
+                toml::from_str(&content).map_err(|e| {
+                    let line = e.span().map(|span| content[..span.start].matches('\n').count() + 1);
+                    parse_error(line, e.message().to_string())
+                })
+            }
+            _ => Err(error::ConfigError::UnsupportedFormat(path.to_path_buf())),
+        }
+    }
+
+    /// Finds every match of a regular expression in a file, line by line
+    ///
+    /// The pattern is compiled once and the file streamed a line at a time, so
+    /// large files aren't loaded whole and matches never span lines. Invalid
+    /// UTF-8 is replaced with U+FFFD, and the file is recorded as processed.
+    #[cfg(feature = "regex")]
+    pub fn grep<P: AsRef<Path>>(
+        &mut self,
+        filename: P,
+        pattern: &str,
+    ) -> Result<Vec<RegexMatch>, error::GrepError> {
+        let regex = regex::Regex::new(pattern).map_err(|e| error::GrepError::BadPattern {
+            pattern: pattern.to_string(),
+            message: e.to_string(),
+        })?;
+        let file_path = self.resolve_path(filename.as_ref())?;
+        let file = File::open(&file_path).map_err(|e| FileProcessorError::from_io(e, &file_path))?;
+        let mut reader = BufReader::new(file);
+
+        let mut matches = Vec::new();
+        let mut buffer = Vec::new();
+        let mut line_number = 0;
+        loop {
+            buffer.clear();
+            let read = reader
+                .read_until(b'\n', &mut buffer)
+                .map_err(|e| FileProcessorError::from_io(e, &file_path))?;
+            if read == 0 {
+                break;
+            }
+            self.bytes_read += read as u64;
+            line_number += 1;
+            let line = String::from_utf8_lossy(&buffer);
+            let line = line.trim_end_matches(['\n', '\r']);
+            matches.extend(regex.find_iter(line).map(|found| RegexMatch {
+                line: line_number,
+                column: line[..found.start()].chars().count() + 1,
+                matched_text: found.as_str().to_string(),
+            }));
+        }
+        self.record_file(filename.as_ref());
+        Ok(matches)
+    }
+
+    /// Reads a file and decodes it from the given text encoding
+    ///
+    /// A leading byte order mark for the encoding is dropped rather than
+    /// decoded as U+FEFF.
+    pub fn read_file_with_encoding<P: AsRef<Path>>(
+        &mut self,
+        filename: P,
+        encoding: Encoding,
+    ) -> error::Result<String> {
+        let file_path = self.resolve_path(filename.as_ref())?;
+        self.check_file_size(&file_path)?;
+        let bytes = fs::read(&file_path).map_err(|e| FileProcessorError::from_io(e, &file_path))?;
+        let text = bytes.strip_prefix(encoding.bom()).unwrap_or(&bytes);
+        let content = encoding
+            .decode(text, self.lossy_decoding)
+            .ok_or_else(|| FileProcessorError::Decode {
+                path: file_path.clone(),
+                encoding: encoding.name(),
+            })?;
+
+        self.bytes_read += bytes.len() as u64;
+        self.record_file(filename.as_ref());
+
+        Ok(content)
+    }
+
+    /// Guesses a file's encoding from its BOM or leading bytes, without recording it
+    ///
+    /// Only the first few kilobytes are inspected; see `DetectedEncoding::detect`.
+    /// The result can be passed to `read_file_with_encoding`.
+    pub fn detect_encoding<P: AsRef<Path>>(&self, filename: P) -> error::Result<DetectedEncoding> {
+        let file_path = self.resolve_path(filename.as_ref())?;
+        let file = File::open(&file_path).map_err(|e| FileProcessorError::from_io(e, &file_path))?;
+        let mut sample = Vec::with_capacity(BINARY_SAMPLE_SIZE);
+        file.take(BINARY_SAMPLE_SIZE as u64)
+            .read_to_end(&mut sample)
+            .map_err(|e| FileProcessorError::from_io(e, &file_path))?;
+        Ok(DetectedEncoding::detect(&sample))
+    }
+
+    /// Rewrites a file in the given encoding and line ending, if that changes it
+    ///
+    /// The current encoding is found with `detect_encoding`, and a BOM is kept
+    /// if the file had one. The file is only written when the bytes differ,
+    /// and the return value says whether it was, so runs can report how many
+    /// files were reformatted.
+    pub fn normalize_file<P: AsRef<Path>>(
+        &mut self,
+        filename: P,
+        encoding: Encoding,
+        ending: LineEnding,
+    ) -> error::Result<bool> {
+        let filename = filename.as_ref();
+        let file_path = self.resolve_path(filename)?;
+        let detected = self.detect_encoding(filename)?;
+        let original = self.read_bytes(filename)?;
+        let text = original.strip_prefix(detected.encoding.bom()).unwrap_or(&original);
+        let content = detected
+            .encoding
+            .decode(text, self.lossy_decoding)
+            .ok_or_else(|| FileProcessorError::Decode {
+                path: file_path.clone(),
+                encoding: detected.encoding.name(),
+            })?;
+        let content = ending.apply(&content);
+
+        let mut normalized = if detected.has_bom { encoding.bom().to_vec() } else { Vec::new() };
+        normalized.extend(encoding.encode(&content).ok_or_else(|| FileProcessorError::Encode {
+            path: file_path.clone(),
+            encoding: encoding.name(),
+        })?);
+        if normalized == original {
+            return Ok(false);
+        }
+        self.write_bytes(filename, &normalized)?;
+        Ok(true)
+    }
+
+    /// Reads raw bytes from a file without UTF-8 decoding
+    pub fn read_bytes<P: AsRef<Path>>(&mut self, filename: P) -> error::Result<Vec<u8>> {
+        let file_path = self.resolve_path(filename.as_ref())?;
+        self.check_file_size(&file_path)?;
+        let mut file = File::open(&file_path)
+            .map_err(|e| FileProcessorError::from_io(e, &file_path))?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+
+        self.bytes_read += data.len() as u64;
+        self.record_file(filename.as_ref());
+
+        Ok(data)
+    }
+
+    /// Writes raw bytes to a file
+    pub fn write_bytes<P: AsRef<Path>>(&mut self, filename: P, data: &[u8]) -> error::Result<()> {
+        let file_path = self.resolve_path(filename.as_ref())?;
+        if self.dry_run {
+            self.plan_write(filename.as_ref(), &file_path, data.len(), &String::from_utf8_lossy(data));
+            return Ok(());
+        }
+        self.prepare_parent(&file_path)?;
+        let _lock = self.lock_for_write(&file_path)?;
+        self.backup_existing(filename.as_ref(), &file_path)?;
+        self.retry(|| File::create(&file_path)?.write_all(data))
+            .map_err(|e| FileProcessorError::from_io(e, &file_path))?;
+
+        self.bytes_written += data.len() as u64;
+        self.invalidate_cache(&file_path);
+        self.record_file(filename.as_ref());
+
+        Ok(())
+    }
+
+    /// Computes the lowercase hex SHA-256 digest of a file
+    ///
+    /// The file is streamed through the hasher in chunks and is not recorded
+    /// as processed.
+    #[cfg(feature = "sha2")]
+    pub fn file_hash<P: AsRef<Path>>(&self, filename: P) -> error::Result<String> {
+        use sha2::{Digest, Sha256};
+
+        let file_path = self.resolve_path(filename.as_ref())?;
+        let file = File::open(&file_path)
+            .map_err(|e| FileProcessorError::from_io(e, &file_path))?;
+        let mut reader = BufReader::new(file);
+        let mut hasher = Sha256::new();
+        let mut buffer = [0u8; 8192];
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+
+        Ok(hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect())
+    }
+
+    /// Reads two files and diffs them line by line
+    pub fn diff_files<P: AsRef<Path>>(&mut self, left: P, right: P) -> error::Result<Vec<DiffLine>> {
+        let left = self.read_file(left)?;
+        let right = self.read_file(right)?;
+        Ok(diff::diff_lines(&left, &right))
+    }
+
+    /// Formats Rust source by piping it through `rustfmt`
+    pub fn format_rust(&self, source: &str) -> Result<String, FormatError> {
+        let mut child = Command::new("rustfmt")
+            .args(["--emit", "stdout", "--edition", "2021"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => FormatError::ToolNotFound("rustfmt".to_string()),
+                _ => FormatError::Io(e),
+            })?;
+
+        // Feed stdin from a thread so a large output can't deadlock the pipes
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let input = source.to_string();
+        let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+        let output = child.wait_with_output()?;
+        writer.join().expect("stdin writer panicked")?;
+
+        if !output.status.success() {
+            return Err(FormatError::Failed {
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            });
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Copies a file within the base path, returning the number of bytes copied
+    ///
+    /// An existing destination is locked and backed up as in `write_file`. In
+    /// dry-run mode the copy is only recorded as a planned write.
+    pub fn copy_file<P: AsRef<Path>, Q: AsRef<Path>>(&mut self, from: P, to: Q) -> error::Result<u64> {
+        let from_path = self.resolve_path(from.as_ref())?;
+        let to_path = self.resolve_path(to.as_ref())?;
+        if self.dry_run {
+            return self.plan_copy(to.as_ref(), &from_path, &to_path);
+        }
+        self.prepare_parent(&to_path)?;
+        let _lock = self.lock_for_write(&to_path)?;
+        self.backup_existing(to.as_ref(), &to_path)?;
+        let copied = fs::copy(&from_path, &to_path).map_err(|e| {
+            let path = if from_path.exists() { &to_path } else { &from_path };
+            FileProcessorError::from_io(e, path)
+        })?;
+
+        self.bytes_written += copied;
+        self.invalidate_cache(&to_path);
+        self.record_file(to.as_ref());
+
+        Ok(copied)
+    }
+
+    /// Moves a file within the base path
+    ///
+    /// An existing destination is locked and backed up as in `write_file`. In
+    /// dry-run mode the move is only recorded as a planned write of the
+    /// destination and a planned delete of the source.
+    pub fn move_file<P: AsRef<Path>, Q: AsRef<Path>>(&mut self, from: P, to: Q) -> error::Result<()> {
+        let from_path = self.resolve_path(from.as_ref())?;
+        let to_path = self.resolve_path(to.as_ref())?;
+        if !from_path.exists() {
+            return Err(FileProcessorError::NotFound(from_path));
+        }
+        if self.dry_run {
+            self.plan_copy(to.as_ref(), &from_path, &to_path)?;
+            self.planned_deletes.push(slash_path(from.as_ref()));
+            return Ok(());
+        }
+        self.prepare_parent(&to_path)?;
+        let _lock = self.lock_for_write(&to_path)?;
+        self.backup_existing(to.as_ref(), &to_path)?;
+        fs::rename(&from_path, &to_path)
+            .map_err(|e| FileProcessorError::from_io(e, &to_path))?;
+
+        self.invalidate_cache(&from_path);
+        self.invalidate_cache(&to_path);
+        self.record_file(to.as_ref());
+
+        Ok(())
+    }
+
+    /// Renames a file within the base path, copying across devices if needed
+    ///
+    /// When `fs::rename` fails because the paths are on different filesystems,
+    /// the file is copied and the original removed. The new name is recorded
+    /// as processed, and with deduplication on the old name is dropped. An
+    /// existing destination is locked and backed up as in `write_file`, and in
+    /// dry-run mode the rename is only planned as in `move_file`.
+    pub fn rename_file<P: AsRef<Path>, Q: AsRef<Path>>(&mut self, from: P, to: Q) -> error::Result<()> {
+        self.rename_with(
+            from.as_ref(),
+            to.as_ref(),
+            |from, to| fs::rename(from, to),
+            |path| fs::remove_file(path),
+        )
+    }
+
+    fn rename_with<F, R>(&mut self, from: &Path, to: &Path, rename: F, remove: R) -> error::Result<()>
+    where
+        F: Fn(&Path, &Path) -> io::Result<()>,
+        R: Fn(&Path) -> io::Result<()>,
+    {
+        let from_path = self.resolve_path(from)?;
+        let to_path = self.resolve_path(to)?;
+        if !from_path.is_file() {
+            return Err(FileProcessorError::NotFound(from_path));
+        }
+        if self.dry_run {
+            self.plan_copy(to, &from_path, &to_path)?;
+            self.planned_deletes.push(slash_path(from));
+            return Ok(());
+        }
+        self.prepare_parent(&to_path)?;
+        let _lock = self.lock_for_write(&to_path)?;
+        self.backup_existing(to, &to_path)?;
+        match rename(&from_path, &to_path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+                fs::copy(&from_path, &to_path).map_err(|e| FileProcessorError::from_io(e, &to_path))?;
+                if let Err(e) = remove(&from_path) {
+                    let _ = fs::remove_file(&to_path);
+                    return Err(FileProcessorError::from_io(e, &from_path));
+                }
+            }
+            Err(e) => return Err(FileProcessorError::from_io(e, &to_path)),
+        }
+
+        self.invalidate_cache(&from_path);
+        self.invalidate_cache(&to_path);
+        if self.dedup_processed {
+            let old_name = slash_path(from);
+            self.processed_files.retain(|name| *name != old_name);
+        }
+        self.record_file(to);
+
+        Ok(())
+    }
+
+    /// Reads every file under a directory whose relative path matches a glob
+    ///
+    /// Hidden directories such as `.git` and paths matching the ignore
+    /// patterns are skipped, and binary files are
+    /// recorded in `skipped_files` instead of being read. Returns the processed
+    /// paths relative to the base path.
+    pub fn process_dir<P: AsRef<Path>>(&mut self, dir: P, pattern: &str) -> error::Result<Vec<String>> {
+        let files = self.list_files(dir, pattern)?;
+        let mut processed = Vec::new();
+        for (index, file) in files.iter().enumerate() {
+            if self.is_probably_binary(file)? {
+                self.skipped_files.push(slash_path(file));
+            } else {
+                self.read_file(file)?;
+                processed.push(slash_path(file));
+            }
+            self.report_progress(index + 1, files.len());
+        }
+        Ok(processed)
+    }
+
+    /// Lists the files `process_dir` would visit, without reading them
+    ///
+    /// Ignore patterns, hidden directories, depth limits, and symlink settings
+    /// apply as usual, but binary files are included and nothing is recorded.
+    /// Paths are relative to the base path, in sorted order.
+    pub fn list_files<P: AsRef<Path>>(&self, dir: P, pattern: &str) -> error::Result<Vec<PathBuf>> {
+        let dir = dir.as_ref();
+        Ok(self
+            .collect_files(dir)?
+            .into_iter()
+            .filter(|file| {
+                let relative = file.strip_prefix(dir).unwrap_or(file);
+                glob::glob_match(pattern, &slash_path(relative))
+            })
+            .collect())
+    }
+
+    /// Analyzes every text file under a directory, returning a report per file
+    ///
+    /// Every file gets language detection and line counts; Rust files are also
+    /// checked for syntax errors and scanned for markers. Files are walked and
+    /// skipped as in `process_dir`.
+    pub fn analyze_dir<P: AsRef<Path>>(&mut self, dir: P) -> error::Result<Vec<FileReport>> {
+        let files = self.collect_files(dir.as_ref())?;
+        let mut reports = Vec::new();
+        for (index, file) in files.iter().enumerate() {
+            if self.is_probably_binary(file)? {
+                self.skipped_files.push(slash_path(file));
+            } else {
+                let source = self.read_file(file)?;
+                let language = language::detect_language(self.resolve_path(file)?).unwrap_or(Language::Unknown);
+                reports.push(FileReport::analyze(slash_path(file), &source, language));
+            }
+            self.report_progress(index + 1, files.len());
+        }
+        Ok(reports)
+    }
+
+    /// Analyzes every text file under a directory on at most `max_threads` threads
+    ///
+    /// Produces the same reports as `analyze_dir`, in the same path order.
+    /// Files are read and analyzed on a dedicated rayon pool through `&self`;
+    /// processed files, skipped files, byte counters, and progress are then
+    /// updated on the calling thread in path order, stopping at the first
+    /// file that failed. A `max_threads` of 0 is treated as 1.
+    #[cfg(feature = "rayon")]
+    pub fn analyze_dir_parallel<P: AsRef<Path>>(
+        &mut self,
+        dir: P,
+        max_threads: usize,
+    ) -> error::Result<Vec<FileReport>> {
+        use rayon::prelude::*;
+
+        let files = self.collect_files(dir.as_ref())?;
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(max_threads.max(1))
+            .build()
+            .map_err(|err| FileProcessorError::Io(io::Error::other(err)))?;
+
+        let processor = &*self;
+        let results: Vec<error::Result<Option<(usize, FileReport)>>> = pool.install(|| {
+            files
+                .par_iter()
+                .map(|file| {
+                    if processor.is_probably_binary(file)? {
+                        return Ok(None);
+                    }
+                    let file_path = processor.resolve_path(file)?;
+                    let source = processor.read_text(&file_path)?;
+                    let language = language::detect_language(&file_path).unwrap_or(Language::Unknown);
+                    Ok(Some((source.len(), FileReport::analyze(slash_path(file), &source, language))))
+                })
+                .collect()
+        });
+
+        let mut reports = Vec::new();
+        for (index, (file, result)) in files.iter().zip(results).enumerate() {
+            match result? {
+                Some((len, report)) => {
+                    self.bytes_read += len as u64;
+                    self.record_file(file);
+                    reports.push(report);
+                }
+                None => self.skipped_files.push(slash_path(file)),
+            }
+            self.report_progress(index + 1, files.len());
+        }
+        Ok(reports)
+    }
+
+    /// Analyzes in-memory source, such as an unsaved editor buffer, without touching disk
+    ///
+    /// Runs the same checks as `analyze_dir`, reporting the path as `<stdin>`.
+    pub fn analyze_source(&self, source: &str, language: Language) -> FileReport {
+        FileReport::analyze(STDIN_PATH.to_string(), source, language)
+    }
+
+    /// Gets the binary files skipped by `process_dir`
+    pub fn skipped_files(&self) -> &[String] {
+        &self.skipped_files
+    }
+
+    /// Guesses whether a file is binary by sampling its first 8KB
+    ///
+    /// A file is binary if the sample contains a NUL byte or more than 30% of
+    /// its bytes are control characters other than common whitespace.
+    pub fn is_probably_binary<P: AsRef<Path>>(&self, path: P) -> error::Result<bool> {
+        let file_path = self.resolve_path(path.as_ref())?;
+        let file = File::open(&file_path).map_err(|e| FileProcessorError::from_io(e, &file_path))?;
+        let mut sample = Vec::with_capacity(BINARY_SAMPLE_SIZE);
+        file.take(BINARY_SAMPLE_SIZE as u64)
+            .read_to_end(&mut sample)
+            .map_err(|e| FileProcessorError::from_io(e, &file_path))?;
+
+        if sample.contains(&0) {
+            return Ok(true);
+        }
+        let non_text = sample
+            .iter()
+            .filter(|&&b| (b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b)) || b == 0x7f)
+            .count();
+        Ok(non_text * 10 > sample.len() * 3)
+    }
+
+    /// Reads every file under a directory that git wouldn't ignore
+    ///
+    /// Inside a git repository, `.gitignore` files in the directory, its
+    /// parents, and nested subdirectories are honored; outside one, every
+    /// file is processed. Hidden directories, ignore patterns, binary files,
+    /// depth limits, and symlinks are handled as in `process_dir`.
+    #[cfg(feature = "ignore")]
+    pub fn process_dir_gitignore<P: AsRef<Path>>(&mut self, dir: P) -> error::Result<Vec<String>> {
+        let dir = dir.as_ref();
+        let root = self.resolve_path(dir)?;
+        let walker = ignore::WalkBuilder::new(&root)
+            .hidden(false)
+            .max_depth(self.max_depth.map(|depth| depth + 1))
+            .follow_links(self.follow_symlinks)
+            .filter_entry(|entry| {
+                let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+                entry.depth() == 0 || !is_dir || !entry.file_name().to_string_lossy().starts_with('.')
+            })
+            .build();
+
+        let mut files = Vec::new();
+        for entry in walker {
+            let entry = entry.map_err(|e| FileProcessorError::Io(io::Error::other(e)))?;
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            let relative = dir.join(entry.path().strip_prefix(&root).unwrap_or(entry.path()));
+            if !self.is_ignored(&relative, false) {
+                files.push(relative);
+            }
+        }
+        files.sort();
+
+        let mut processed = Vec::new();
+        for file in files {
+            if self.is_probably_binary(&file)? {
+                self.skipped_files.push(slash_path(&file));
+                continue;
+            }
+            self.read_file(&file)?;
+            processed.push(slash_path(&file));
+        }
+        Ok(processed)
+    }
+
+    /// Limits `watch` to files whose relative path matches a glob
+    ///
+    /// Without a pattern, any file with a recognized source extension is watched.
+    pub fn with_watch_pattern(mut self, pattern: &str) -> Self {
+        self.watch_pattern = Some(pattern.to_string());
+        self
+    }
+
+    /// Watches a directory and calls `on_change` for each modified source file
+    ///
+    /// Blocks until the watcher shuts down. Changes are debounced so a burst
+    /// of writes to the same file is reported once, with the path relative to
+    /// the base path. Hidden and ignored paths are never reported.
+    #[cfg(feature = "notify")]
+    pub fn watch<P: AsRef<Path>>(&mut self, dir: P, mut on_change: impl FnMut(&Path)) -> error::Result<()> {
+        use notify::{EventKind, RecursiveMode, Watcher};
+        use std::collections::BTreeSet;
+        use std::sync::mpsc::{self, RecvTimeoutError};
+
+        let root = self.resolve_path(dir.as_ref())?;
+        let base = fs::canonicalize(&self.base_path)
+            .map_err(|e| FileProcessorError::from_io(e, &self.base_path))?;
+        let watch_error = |e: notify::Error| FileProcessorError::Io(io::Error::other(e));
+
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender).map_err(watch_error)?;
+        watcher.watch(&root, RecursiveMode::Recursive).map_err(watch_error)?;
+
+        let mut pending = BTreeSet::new();
+        loop {
+            let event = if pending.is_empty() {
+                receiver.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            } else {
+                receiver.recv_timeout(WATCH_DEBOUNCE)
+            };
+            match event {
+                Ok(event) => {
+                    let event = event.map_err(watch_error)?;
+                    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                        let changed = event.paths.iter().filter_map(|path| {
+                            let relative = path.strip_prefix(&base).ok()?;
+                            (path.is_file() && self.is_watched(relative)).then(|| relative.to_path_buf())
+                        });
+                        pending.extend(changed);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    for path in std::mem::take(&mut pending) {
+                        on_change(&path);
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+    }
+
+    #[cfg(feature = "notify")]
+    fn is_watched(&self, relative: &Path) -> bool {
+        let hidden = relative
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'));
+        if hidden || self.is_ignored(relative, false) {
+            return false;
+        }
+        match &self.watch_pattern {
+            Some(pattern) => glob::glob_match(pattern, &slash_path(relative)),
+            None => relative.extension().is_some_and(|ext| {
+                Language::from_extension(&ext.to_string_lossy()) != Language::Unknown
+            }),
+        }
+    }
+
+    /// Recursively lists files under a directory, sorted, skipping hidden and ignored paths
+    ///
+    /// Symlinks are skipped unless `follow_symlinks` is set, and descent stops
+    /// at `max_depth`.
+    fn collect_files(&self, dir: &Path) -> error::Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let mut pending = vec![(dir.to_path_buf(), 0)];
+        let mut visited = HashSet::new();
+
+        while let Some((current, depth)) = pending.pop() {
+            let resolved = self.resolve_path(&current)?;
+            if !visited.insert(resolved.clone()) {
+                continue;
+            }
+            let entries = fs::read_dir(&resolved)
+                .map_err(|e| FileProcessorError::from_io(e, &resolved))?;
+            for entry in entries {
+                let entry = entry?;
+                let mut file_type = entry.file_type()?;
+                let relative = current.join(entry.file_name());
+                if file_type.is_symlink() {
+                    if !self.follow_symlinks || self.resolve_path(&relative).is_err() {
+                        continue;
+                    }
+                    // Broken links have no target to process
+                    match fs::metadata(entry.path()) {
+                        Ok(metadata) => file_type = metadata.file_type(),
+                        Err(_) => continue,
+                    }
+                }
+                if self.is_ignored(&relative, file_type.is_dir()) {
+                    continue;
+                }
+                if file_type.is_dir() {
+                    let hidden = entry.file_name().to_string_lossy().starts_with('.');
+                    if !hidden && self.max_depth.is_none_or(|max| depth < max) {
+                        pending.push((relative, depth + 1));
+                    }
+                } else if file_type.is_file() {
+                    files.push(relative);
+                }
+            }
+        }
+
+        files.sort();
+        Ok(files)
+    }
+
+    /// Reads content from a file without blocking the async runtime
+    #[cfg(feature = "tokio")]
+    pub async fn read_file_async<P: AsRef<Path>>(&mut self, filename: P) -> error::Result<String> {
+        let file_path = self.resolve_path(filename.as_ref())?;
+        self.check_file_size(&file_path)?;
+        let content = tokio::fs::read_to_string(&file_path)
+            .await
+            .map_err(|e| FileProcessorError::from_io(e, &file_path))?;
+
+        self.bytes_read += content.len() as u64;
+        self.record_file(filename.as_ref());
+
+        Ok(content)
+    }
+
+    /// Writes content to a file without blocking the async runtime
+    #[cfg(feature = "tokio")]
+    pub async fn write_file_async<P: AsRef<Path>>(&mut self, filename: P, content: &str) -> error::Result<()> {
+        let content = self.prepare_content(content);
+        let file_path = self.resolve_path(filename.as_ref())?;
+        if self.dry_run {
+            self.plan_write(filename.as_ref(), &file_path, content.len(), &content);
+            return Ok(());
+        }
+        self.prepare_parent(&file_path)?;
+        self.backup_existing(filename.as_ref(), &file_path)?;
+        tokio::fs::write(&file_path, content.as_bytes())
+            .await
+            .map_err(|e| FileProcessorError::from_io(e, &file_path))?;
+
+        self.bytes_written += content.len() as u64;
+        self.invalidate_cache(&file_path);
+        self.record_file(filename.as_ref());
+
+        Ok(())
+    }
+
+    /// Clears the processed-files log and byte counters, keeping configuration
+    ///
+    /// Returns the number of processed-file entries that were cleared.
+    pub fn reset(&mut self) -> usize {
+        let cleared = self.processed_files.len();
+        self.processed_files.clear();
+        self.skipped_files.clear();
+        self.deleted_files.clear();
+        self.by_language.clear();
+        self.bytes_read = 0;
+        self.bytes_written = 0;
+        cleared
+    }
+
+    /// Records a file as processed, counting it towards its language
+    fn record_file(&mut self, filename: &Path) {
+        let name = slash_path(filename);
+        if self.dedup_processed && self.processed_files.contains(&name) {
+            return;
+        }
+        let language = self
+            .resolve_path(filename)
+            .ok()
+            .and_then(language::detect_language)
+            .unwrap_or(Language::Unknown);
+        *self.by_language.entry(language.display_name().to_string()).or_default() += 1;
+        self.processed_files.push(name);
+    }
+
+    /// Gets typed processing statistics
+    pub fn stats(&self) -> Stats {
+        Stats {
+            base_path: self.base_path.clone(),
+            processed_files_count: self.processed_files.len(),
+            files: self.processed_files.clone(),
+            bytes_read: self.bytes_read,
+            bytes_written: self.bytes_written,
+            by_language: self.by_language.clone(),
+        }
+    }
+
+    /// Gets processing statistics as a JSON-style map
+    pub fn get_stats(&self) -> HashMap<String, serde_json::Value> {
+        self.stats().to_map()
+    }
+}
+
+/// Processing statistics for a FileProcessor
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stats {
+    pub base_path: String,
+    pub processed_files_count: usize,
+    pub files: Vec<String>,
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+    /// Processed-file counts keyed by language display name
+    #[cfg_attr(feature = "serde", serde(default, serialize_with = "serialize_sorted"))]
+    pub by_language: HashMap<String, usize>,
+}
+
+/// A regular expression match found by `FileProcessor::grep`
+#[cfg(feature = "regex")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegexMatch {
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column of the first matched character
+    pub column: usize,
+    pub matched_text: String,
+}
+
+/// Feedback gathered for one file by `analyze_dir`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileReport {
+    pub path: String,
+    pub language: Language,
+    /// Whether the file parses, or `None` for non-Rust files
+    pub syntax_ok: Option<bool>,
+    pub markers: Vec<MarkerHit>,
+    pub lines: LineCount,
+}
+
+/// Path reported for source analyzed from memory rather than a file
+pub const STDIN_PATH: &str = "<stdin>";
+
+impl FileReport {
+    /// Runs the language-appropriate checks on source text
+    fn analyze(path: String, source: &str, language: Language) -> FileReport {
+        let is_rust = language == Language::Rust;
+        FileReport {
+            path,
+            language,
+            syntax_ok: is_rust.then(|| syntax::validate_rust_syntax(source).is_ok()),
+            markers: if is_rust { markers::find_markers(source) } else { Vec::new() },
+            lines: metrics::count_lines(source, language),
+        }
+    }
+}
+
+/// Serializes a map with its keys in order so output is stable
+#[cfg(feature = "serde")]
+fn serialize_sorted<S: serde::Serializer>(map: &HashMap<String, usize>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(map.iter().collect::<std::collections::BTreeMap<_, _>>())
+}
+
+/// Base path reported by merged stats from processors with different roots
+pub const MULTIPLE_BASE_PATHS: &str = "<multiple>";
+
+impl Stats {
+    /// Combines another run's statistics into these, such as from per-thread processors
+    ///
+    /// Counts and byte counters are summed and file lists are unioned without
+    /// duplicates. The base path is kept only if both agree, otherwise it
+    /// becomes `MULTIPLE_BASE_PATHS`.
+    pub fn merge(&mut self, other: &Stats) {
+        if self.base_path != other.base_path {
+            self.base_path = MULTIPLE_BASE_PATHS.to_string();
+        }
+        self.processed_files_count += other.processed_files_count;
+        for file in &other.files {
+            if !self.files.contains(file) {
+                self.files.push(file.clone());
+            }
+        }
+        self.bytes_read += other.bytes_read;
+        self.bytes_written += other.bytes_written;
+        for (language, count) in &other.by_language {
+            *self.by_language.entry(language.clone()).or_default() += count;
+        }
+    }
+
+    /// Converts the statistics into the map returned by `get_stats`
+    pub fn to_map(&self) -> HashMap<String, serde_json::Value> {
+        let mut stats = HashMap::new();
+        stats.insert("base_path".to_string(), 
+                    serde_json::Value::String(self.base_path.clone()));
+        stats.insert("processed_files_count".to_string(), 
+                    serde_json::Value::Number(self.processed_files_count.into()));
+        stats.insert("files".to_string(), 
+                    serde_json::Value::Array(
+                        self.files.iter()
+                            .map(|f| serde_json::Value::String(f.clone()))
+                            .collect()
+                    ));
+        stats.insert("bytes_read".to_string(), 
+                    serde_json::Value::Number(self.bytes_read.into()));
+        stats.insert("bytes_written".to_string(), 
+                    serde_json::Value::Number(self.bytes_written.into()));
+        stats.insert("by_language".to_string(),
+                    serde_json::Value::Object(
+                        self.by_language.iter()
+                            .map(|(language, count)| (language.clone(), (*count).into()))
+                            .collect()
+                    ));
+        stats
+    }
+}
+
+/// Renders a relative path canonically with `/` separators on every platform
+///
+/// `.` segments and redundant separators are dropped and `..` is collapsed,
+/// so `./a/b.rs`, `a//b.rs`, and `a\b.rs` on Windows all become `a/b.rs`.
+fn slash_path(path: &Path) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+            Component::ParentDir => {
+                parts.pop();
+            }
+            _ => {}
+        }
+    }
+    parts.join("/")
+}
+
+/// Reads everything a reader yields, recording failed reads rather than stopping
+fn read_chunks<R: Read>(mut reader: R, errors: &mut Vec<FileProcessorError>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut buffer = [0u8; 8192];
+    let mut failures = 0;
+    while failures < MAX_READ_ERRORS {
+        match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => {
+                bytes.extend_from_slice(&buffer[..read]);
+                failures = 0;
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => {
+                errors.push(FileProcessorError::Io(err));
+                failures += 1;
+            }
+        }
+    }
+    bytes
+}
+
+/// Checks whether an IO error is worth retrying
+fn is_transient(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+/// Builds a unique hidden temp path next to the given file
+fn temp_sibling(path: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let unique = COUNTER.fetch_add(1, Ordering::Relaxed);
+    path.with_file_name(format!(".{}.{}.{}.tmp", name, std::process::id(), unique))
+}
+
+/// Processes command line arguments
+pub fn process_arguments(args: Vec<String>) -> Vec<String> {
+    args.into_iter()
+        .skip(1) // Skip program name
+        .filter(|arg| !arg.trim().is_empty())
+        .map(|arg| arg.to_uppercase())
+        .collect()
+}
+
+/// Command line arguments split into positionals, flags, and options
+#[derive(Debug, Default, PartialEq)]
+pub struct ParsedArgs {
+    pub positional: Vec<String>,
+    pub flags: HashSet<String>,
+    pub options: HashMap<String, String>,
+}
+
+/// Parser for positionals, `--flag`s, and `--key=value` options
+///
+/// Without configured value flags, `--key value` is treated as an option
+/// whenever the next argument isn't itself a flag. Everything after a bare
+/// `--` is positional.
+#[derive(Debug, Clone, Default)]
+pub struct ArgParser {
+    value_flags: Option<HashSet<String>>,
+}
+
+impl ArgParser {
+    /// Creates a parser that infers which flags take values
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares the flags that take a value, making every other flag boolean
+    ///
+    /// A value flag without a value, or a boolean flag given `--flag=value`,
+    /// is then reported as an error instead of being guessed at.
+    pub fn with_value_flags(mut self, flags: HashSet<String>) -> Self {
+        self.value_flags = Some(flags);
+        self
+    }
+
+    /// Parses arguments, skipping the program name
+    pub fn parse(&self, args: Vec<String>) -> Result<ParsedArgs, ArgError> {
+        let mut parsed = ParsedArgs::default();
+        let mut args = args.into_iter().skip(1).peekable(); // Skip program name
+
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                parsed.positional.extend(args.by_ref());
+                break;
+            }
+            let Some(name) = arg.strip_prefix("--") else {
+                parsed.positional.push(arg);
+                continue;
+            };
+            let (name, inline_value) = match name.split_once('=') {
+                Some((key, value)) => (key, Some(value.to_string())),
+                None => (name, None),
+            };
+            let takes_value = self.value_flags.as_ref().map(|flags| flags.contains(name));
+
+            match (takes_value, inline_value) {
+                (Some(false), Some(_)) => return Err(ArgError::UnexpectedValue(name.to_string())),
+                (Some(false), None) => {
+                    parsed.flags.insert(name.to_string());
+                }
+                (_, Some(value)) => {
+                    parsed.options.insert(name.to_string(), value);
+                }
+                (_, None) => match args.next_if(|next| !next.starts_with("--")) {
+                    Some(value) => {
+                        parsed.options.insert(name.to_string(), value);
+                    }
+                    None if takes_value == Some(true) => {
+                        return Err(ArgError::MissingValue(name.to_string()))
+                    }
+                    None => {
+                        parsed.flags.insert(name.to_string());
+                    }
+                },
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+/// Parses command line arguments, inferring which flags take values
+pub fn parse_arguments(args: Vec<String>) -> Result<ParsedArgs, ArgError> {
+    ArgParser::new().parse(args)
+}
+
+/// Main function demonstrating the module functionality
+fn main() -> io::Result<()> {
+    // Create a person
+    let person = Person::new("Alice Smith".to_string(), 25)
+        .with_email("alice@example.com".to_string());
+    
+    println!("{}", person.greet());
+    println!("Is adult: {}", person.is_adult());
+
+    // File processing example
+    let processor = FileProcessor::new(".");
+    
+    // Process command line arguments
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 {
+        match parse_arguments(args) {
+            Ok(parsed_args) => println!("Parsed arguments: {:?}", parsed_args),
+            Err(err) => eprintln!("Invalid arguments: {}", err),
+        }
+    }
+
+    // Display stats
+    let stats = processor.get_stats();
+    println!("Processor stats: {}", serde_json::to_string_pretty(&stats)?);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("file_processor_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_person_creation() {
+        let person = Person::new("John Doe".to_string(), 30);
+        assert_eq!(person.name, "John Doe");
+        assert_eq!(person.age, 30);
+        assert!(person.email.is_none());
+    }
+
+    #[test]
+    fn test_person_with_email() {
+        let person = Person::new("Jane Doe".to_string(), 25)
+            .with_email("jane@example.com".to_string());
+        assert!(person.email.is_some());
+        assert_eq!(person.email.unwrap(), "jane@example.com");
+    }
+
+    #[test]
+    fn test_is_adult() {
+        let adult = Person::new("Adult".to_string(), 18);
+        let minor = Person::new("Minor".to_string(), 17);
+        
+        assert!(adult.is_adult());
+        assert!(!minor.is_adult());
+    }
+
+    #[test]
+    fn test_process_arguments() {
+        let args = vec![
+            "program_name".to_string(),
+            "hello".to_string(),
+            "world".to_string(),
+            "".to_string(),
+            "rust".to_string(),
+        ];
+        
+        let processed = process_arguments(args);
+        assert_eq!(processed, vec!["HELLO", "WORLD", "RUST"]);
+    }
+
+    #[test]
+    fn test_read_missing_file_is_not_found() {
+        let dir = temp_dir("missing");
+        let mut processor = FileProcessor::new(&dir);
+
+        let err = processor.read_file("missing.txt").unwrap_err();
+        assert!(matches!(err, FileProcessorError::NotFound(path) if path.ends_with("missing.txt")));
+    }
+
+    #[test]
+    fn test_read_escaping_path_is_outside_base() {
+        let dir = temp_dir("escape_error");
+        let mut processor = FileProcessor::new(&dir);
+
+        let err = processor.read_file("../outside.txt").unwrap_err();
+        assert!(matches!(err, FileProcessorError::OutsideBasePath(_)));
+        assert!(processor.processed_files.is_empty());
+    }
+
+    #[test]
+    fn test_rejects_parent_dir_escape() {
+        let dir = temp_dir("parent_escape");
+        let mut processor = FileProcessor::new(dir.join("base"));
+        std::fs::create_dir_all(dir.join("base")).unwrap();
+        std::fs::write(dir.join("escape"), "secret").unwrap();
+
+        let err = processor.read_file("../escape").unwrap_err();
+        assert!(matches!(err, FileProcessorError::OutsideBasePath(_)));
+        let err = processor.write_file("../escape", "overwritten").unwrap_err();
+        assert!(matches!(err, FileProcessorError::OutsideBasePath(_)));
+        assert_eq!(std::fs::read_to_string(dir.join("escape")).unwrap(), "secret");
+    }
+
+    #[test]
+    fn test_rejects_absolute_path() {
+        let dir = temp_dir("absolute");
+        let mut processor = FileProcessor::new(&dir);
+
+        let err = processor.read_file("/tmp/x").unwrap_err();
+        assert!(matches!(err, FileProcessorError::OutsideBasePath(_)));
+        let err = processor.write_file("/tmp/x", "content").unwrap_err();
+        assert!(matches!(err, FileProcessorError::OutsideBasePath(_)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rejects_symlink_escape() {
+        let dir = temp_dir("symlink_escape");
+        std::fs::create_dir_all(dir.join("base")).unwrap();
+        std::fs::create_dir_all(dir.join("outside")).unwrap();
+        std::os::unix::fs::symlink(dir.join("outside"), dir.join("base/link")).unwrap();
+        let mut processor = FileProcessor::new(dir.join("base"));
+
+        let err = processor.write_file("link/file.txt", "content").unwrap_err();
+        assert!(matches!(err, FileProcessorError::OutsideBasePath(_)));
+        assert!(!dir.join("outside/file.txt").exists());
+    }
+
+    #[test]
+    fn test_nested_path_within_base() {
+        let dir = temp_dir("nested");
+        std::fs::create_dir_all(dir.join("sub/dir")).unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        processor.write_file("sub/dir/file.txt", "nested").unwrap();
+        assert_eq!(processor.read_file("sub/dir/file.txt").unwrap(), "nested");
+        assert_eq!(processor.read_file("sub/../sub/dir/file.txt").unwrap(), "nested");
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_round_trip() {
+        let dir = temp_dir("async_round_trip");
+        let mut processor = FileProcessor::new(&dir);
+
+        processor.write_file_async("async.txt", "hello async").await.unwrap();
+        let content = processor.read_file_async("async.txt").await.unwrap();
+        assert_eq!(content, "hello async");
+
+        let stats = processor.get_stats();
+        assert_eq!(stats["processed_files_count"], serde_json::json!(2));
+        assert_eq!(stats["files"], serde_json::json!(["async.txt", "async.txt"]));
+    }
+
+    #[test]
+    fn test_read_file_lines_streams_large_file() {
+        let dir = temp_dir("lines");
+        {
+            let mut file = File::create(dir.join("large.log")).unwrap();
+            for i in 0..10_000 {
+                writeln!(file, "log line {}", i).unwrap();
+            }
+        }
+        let mut processor = FileProcessor::new(&dir);
+
+        let mut count = 0;
+        for line in processor.read_file_lines("large.log").unwrap() {
+            let line = line.unwrap();
+            assert_eq!(line, format!("log line {}", count));
+            count += 1;
+        }
+        assert_eq!(count, 10_000);
+        assert_eq!(processor.processed_files, vec!["large.log"]);
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let dir = temp_dir("bytes");
+        let mut processor = FileProcessor::new(&dir);
+        let data: Vec<u8> = vec![0x89, 0x50, 0x4e, 0x47, 0x00, 0xff, 0xfe, 0xc3, 0x28];
+        assert!(String::from_utf8(data.clone()).is_err());
+
+        processor.write_bytes("image.bin", &data).unwrap();
+        assert_eq!(processor.read_bytes("image.bin").unwrap(), data);
+        assert_eq!(processor.processed_files, vec!["image.bin", "image.bin"]);
+    }
+
+    #[test]
+    fn test_atomic_write_never_exposes_partial_content() {
+        let dir = temp_dir("atomic");
+        let old = "old".repeat(100_000);
+        let new = "new".repeat(100_000);
+        std::fs::write(dir.join("target.txt"), &old).unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        let reader_path = dir.join("target.txt");
+        let (old_seen, new_seen) = (old.clone(), new.clone());
+        let done = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let reader_done = done.clone();
+        let reader = std::thread::spawn(move || {
+            while !reader_done.load(Ordering::Relaxed) {
+                let content = std::fs::read_to_string(&reader_path).unwrap();
+                assert!(content == old_seen || content == new_seen);
+            }
+        });
+
+        for i in 0..20 {
+            let content = if i % 2 == 0 { &new } else { &old };
+            processor.write_file_atomic("target.txt", content).unwrap();
+        }
+        done.store(true, Ordering::Relaxed);
+        reader.join().unwrap();
+
+        assert_eq!(std::fs::read_to_string(dir.join("target.txt")).unwrap(), old);
+        let leftovers = std::fs::read_dir(&dir).unwrap().count();
+        assert_eq!(leftovers, 1);
+    }
+
+    #[test]
+    fn test_atomic_write_missing_directory() {
+        let dir = temp_dir("atomic_missing");
+        let mut processor = FileProcessor::new(&dir);
+
+        let err = processor.write_file_atomic("missing/file.txt", "content").unwrap_err();
+        assert!(matches!(err, FileProcessorError::ParentMissing(path) if path.ends_with("missing")));
+        assert!(!dir.join("missing").exists());
+    }
+
+    #[test]
+    fn test_process_dir_matches_glob() {
+        let dir = temp_dir("process_dir");
+        std::fs::create_dir_all(dir.join("project/src/nested")).unwrap();
+        std::fs::create_dir_all(dir.join("project/.git")).unwrap();
+        std::fs::write(dir.join("project/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.join("project/src/lib.rs"), "pub fn lib() {}").unwrap();
+        std::fs::write(dir.join("project/src/nested/deep.rs"), "").unwrap();
+        std::fs::write(dir.join("project/src/notes.txt"), "notes").unwrap();
+        std::fs::write(dir.join("project/.git/hook.rs"), "").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        let processed = processor.process_dir("project", "**/*.rs").unwrap();
+        assert_eq!(
+            processed,
+            vec!["project/main.rs", "project/src/lib.rs", "project/src/nested/deep.rs"]
+        );
+        assert_eq!(processor.processed_files.len(), 3);
+    }
+
+    #[test]
+    fn test_cache_hit_and_mtime_bust() {
+        let dir = temp_dir("cache");
+        let path = dir.join("cached.txt");
+        std::fs::write(&path, "first").unwrap();
+        let original_mtime = std::fs::metadata(&path).unwrap().modified().unwrap();
+        let mut processor = FileProcessor::new(&dir);
+        processor.enable_cache();
+
+        assert_eq!(processor.read_file("cached.txt").unwrap(), "first");
+
+        // Same mtime means the cached content is served without re-reading
+        std::fs::write(&path, "second").unwrap();
+        File::options().write(true).open(&path).unwrap().set_modified(original_mtime).unwrap();
+        assert_eq!(processor.read_file("cached.txt").unwrap(), "first");
+
+        // Touching the mtime busts the entry
+        let touched = original_mtime + std::time::Duration::from_secs(10);
+        File::options().write(true).open(&path).unwrap().set_modified(touched).unwrap();
+        assert_eq!(processor.read_file("cached.txt").unwrap(), "second");
+    }
+
+    #[test]
+    fn test_write_invalidates_cache() {
+        let dir = temp_dir("cache_write");
+        let mut processor = FileProcessor::new(&dir);
+        processor.enable_cache();
+
+        processor.write_file("file.txt", "before").unwrap();
+        let mtime = std::fs::metadata(dir.join("file.txt")).unwrap().modified().unwrap();
+        assert_eq!(processor.read_file("file.txt").unwrap(), "before");
+
+        processor.write_file("file.txt", "after").unwrap();
+        File::options().write(true).open(dir.join("file.txt")).unwrap().set_modified(mtime).unwrap();
+        assert_eq!(processor.read_file("file.txt").unwrap(), "after");
+    }
+
+    #[test]
+    fn test_max_file_size() {
+        let dir = temp_dir("max_size");
+        std::fs::write(dir.join("large.txt"), "x".repeat(2048)).unwrap();
+        std::fs::write(dir.join("small.txt"), "x".repeat(500)).unwrap();
+        let mut processor = FileProcessor::new(&dir).with_max_file_size(1024);
+
+        let err = processor.read_file("large.txt").unwrap_err();
+        assert!(matches!(
+            err,
+            FileProcessorError::FileTooLarge { size: 2048, limit: 1024, .. }
+        ));
+        assert!(processor.read_bytes("large.txt").is_err());
+        assert_eq!(processor.read_file("small.txt").unwrap().len(), 500);
+        assert_eq!(processor.processed_files, vec!["small.txt"]);
+    }
+
+    #[test]
+    fn test_byte_counters_in_stats() {
+        let dir = temp_dir("byte_counters");
+        let mut processor = FileProcessor::new(&dir);
+
+        processor.write_file("a.txt", "hello").unwrap();
+        processor.write_bytes("b.bin", &[0u8; 7]).unwrap();
+        processor.read_file("a.txt").unwrap();
+
+        let stats = processor.get_stats();
+        assert_eq!(stats["bytes_written"], serde_json::json!(12));
+        assert_eq!(stats["bytes_read"], serde_json::json!(5));
+        assert_eq!(stats["processed_files_count"], serde_json::json!(3));
+    }
+
+    #[test]
+    fn test_repeated_reads_without_dedup() {
+        let dir = temp_dir("no_dedup");
+        std::fs::write(dir.join("a.txt"), "a").unwrap();
+        std::fs::write(dir.join("b.txt"), "b").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        for name in ["a.txt", "b.txt", "a.txt"] {
+            processor.read_file(name).unwrap();
+        }
+        assert_eq!(processor.processed_files, vec!["a.txt", "b.txt", "a.txt"]);
+    }
+
+    #[test]
+    fn test_repeated_reads_with_dedup() {
+        let dir = temp_dir("dedup");
+        std::fs::write(dir.join("a.txt"), "a").unwrap();
+        std::fs::write(dir.join("b.txt"), "b").unwrap();
+        let mut processor = FileProcessor::new(&dir).with_dedup_processed(true);
+
+        for name in ["b.txt", "a.txt", "b.txt", "a.txt"] {
+            processor.read_file(name).unwrap();
+        }
+        assert_eq!(processor.processed_files, vec!["b.txt", "a.txt"]);
+        assert_eq!(processor.get_stats()["processed_files_count"], serde_json::json!(2));
+    }
+
+    #[test]
+    fn test_reset_between_batches() {
+        let dir = temp_dir("reset");
+        std::fs::write(dir.join("a.txt"), "aaaa").unwrap();
+        std::fs::write(dir.join("big.txt"), "x".repeat(64)).unwrap();
+        let mut processor = FileProcessor::new(&dir).with_max_file_size(16);
+
+        processor.read_file("a.txt").unwrap();
+        processor.write_file("b.txt", "bb").unwrap();
+        assert_eq!(processor.reset(), 2);
+
+        let stats = processor.get_stats();
+        assert_eq!(stats["processed_files_count"], serde_json::json!(0));
+        assert_eq!(stats["bytes_read"], serde_json::json!(0));
+        assert_eq!(stats["bytes_written"], serde_json::json!(0));
+
+        processor.read_file("b.txt").unwrap();
+        assert!(processor.read_file("big.txt").is_err());
+        assert_eq!(processor.processed_files, vec!["b.txt"]);
+        assert_eq!(processor.get_stats()["bytes_read"], serde_json::json!(2));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_person_json_round_trip_with_email() {
+        let person = Person::new("Jane Doe".to_string(), 25)
+            .with_email("jane@example.com".to_string());
+
+        let json = serde_json::to_string(&person).unwrap();
+        assert_eq!(json, r#"{"name":"Jane Doe","age":25,"email":"jane@example.com"}"#);
+        let parsed = Person::from_json(&json).unwrap();
+        assert_eq!(parsed.name, "Jane Doe");
+        assert_eq!(parsed.age, 25);
+        assert_eq!(parsed.email.as_deref(), Some("jane@example.com"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_person_json_round_trip_without_email() {
+        let person = Person::new("John Doe".to_string(), 30);
+
+        let json = serde_json::to_string(&person).unwrap();
+        assert_eq!(json, r#"{"name":"John Doe","age":30}"#);
+        let parsed = Person::from_json(&json).unwrap();
+        assert_eq!(parsed.name, "John Doe");
+        assert!(parsed.email.is_none());
+        assert!(Person::from_json(r#"{"name":"Null","age":1,"email":null}"#).unwrap().email.is_none());
+    }
+
+    #[test]
+    fn test_try_with_email_valid() {
+        let person = Person::new("Jane Doe".to_string(), 25)
+            .try_with_email("jane@example.com".to_string())
+            .unwrap();
+        assert_eq!(person.email.as_deref(), Some("jane@example.com"));
+    }
+
+    #[test]
+    fn test_try_with_email_missing_at() {
+        let err = Person::new("Jane Doe".to_string(), 25)
+            .try_with_email("not-an-email".to_string())
+            .unwrap_err();
+        assert_eq!(err, PersonError::InvalidEmail("not-an-email".to_string()));
+    }
+
+    #[test]
+    fn test_try_with_email_empty_domain() {
+        let result = Person::new("Jane Doe".to_string(), 25).try_with_email("jane@".to_string());
+        assert!(matches!(result, Err(PersonError::InvalidEmail(_))));
+        assert!(Person::new("Jane Doe".to_string(), 25)
+            .try_with_email("a@b@c".to_string())
+            .is_err());
+    }
+
+    #[test]
+    fn test_with_email_ignores_invalid_input() {
+        let person = Person::new("Jane Doe".to_string(), 25)
+            .with_email("jane@example.com".to_string())
+            .with_email("not-an-email".to_string());
+        assert_eq!(person.email.as_deref(), Some("jane@example.com"));
+    }
+
+    #[test]
+    fn test_from_birth_year() {
+        let person = Person::from_birth_year("Alice".to_string(), 1990, 2024).unwrap();
+        assert_eq!(person.age, 34);
+        assert!(person.is_adult());
+    }
+
+    #[test]
+    fn test_from_birth_year_in_future() {
+        let err = Person::from_birth_year("Future".to_string(), 2030, 2024).unwrap_err();
+        assert_eq!(err, PersonError::InvalidAge(-6));
+        assert!(Person::from_birth_year("Ancient".to_string(), 1800, 2024).is_err());
+    }
+
+    #[test]
+    fn test_adult_boundary() {
+        let adult = Person::from_birth_year("Adult".to_string(), 2006, 2024).unwrap();
+        let minor = Person::from_birth_year("Minor".to_string(), 2007, 2024).unwrap();
+        assert_eq!(adult.age, ADULT_AGE);
+        assert!(adult.is_adult());
+        assert!(!minor.is_adult());
+    }
+
+    #[test]
+    fn test_try_new_age_range() {
+        assert!(Person::try_new("Old".to_string(), MAX_AGE).is_ok());
+        assert_eq!(
+            Person::try_new("Too Old".to_string(), 5000).unwrap_err(),
+            PersonError::InvalidAge(5000)
+        );
+    }
+
+    #[test]
+    fn test_greet_in_spanish_and_french() {
+        let person = Person::new("Alice".to_string(), 25);
+        assert_eq!(person.greet_in("es"), "Hola, soy Alice y tengo 25 años");
+        assert_eq!(person.greet_in("fr"), "Bonjour, je suis Alice et j'ai 25 ans");
+        assert_eq!(person.greet_in("es-MX"), person.greet_in("es"));
+    }
+
+    #[test]
+    fn test_greet_in_unknown_locale_falls_back_to_english() {
+        let person = Person::new("Alice".to_string(), 25);
+        assert_eq!(person.greet_in("xx"), "Hello, I'm Alice and I'm 25 years old");
+        assert_eq!(person.greet_in(""), person.greet());
+    }
+
+    #[test]
+    fn test_person_equality() {
+        let alice = Person::new("Alice Smith".to_string(), 25);
+        assert_eq!(alice, Person::new("Alice Smith".to_string(), 25));
+        assert_ne!(alice, Person::new("Alice Smith".to_string(), 26));
+        assert_ne!(alice, alice.clone().with_email("alice@example.com".to_string()));
+
+        let people: std::collections::HashSet<Person> =
+            [alice.clone(), alice.clone()].into_iter().collect();
+        assert_eq!(people.len(), 1);
+    }
+
+    #[test]
+    fn test_person_display() {
+        let alice = Person::new("Alice Smith".to_string(), 25);
+        assert_eq!(alice.to_string(), "Alice Smith (25)");
+        let alice = alice.with_email("alice@example.com".to_string());
+        assert_eq!(alice.to_string(), "Alice Smith (25) <alice@example.com>");
+    }
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_arguments_mixed() {
+        let parsed = parse_arguments(args(&[
+            "program_name", "input.rs", "--verbose", "--level=3", "--output", "out.txt", "extra",
+        ]))
+        .unwrap();
+        assert_eq!(parsed.positional, vec!["input.rs", "extra"]);
+        assert_eq!(parsed.flags, HashSet::from(["verbose".to_string()]));
+        assert_eq!(parsed.options["level"], "3");
+        assert_eq!(parsed.options["output"], "out.txt");
+    }
+
+    #[test]
+    fn test_parse_arguments_trailing_flag() {
+        let parsed = parse_arguments(args(&["program_name", "--dry-run", "--force"])).unwrap();
+        assert!(parsed.positional.is_empty());
+        assert!(parsed.flags.contains("dry-run"));
+        assert!(parsed.flags.contains("force"));
+        assert!(parsed.options.is_empty());
+    }
+
+    #[test]
+    fn test_parse_arguments_terminator() {
+        let parsed = parse_arguments(args(&["program_name", "--fast", "--", "--not-a-flag", "file"])).unwrap();
+        assert_eq!(parsed.flags, HashSet::from(["fast".to_string()]));
+        assert_eq!(parsed.positional, vec!["--not-a-flag", "file"]);
+    }
+
+    fn strict_parser() -> ArgParser {
+        ArgParser::new().with_value_flags(HashSet::from(["output".to_string()]))
+    }
+
+    #[test]
+    fn test_value_flag_without_value() {
+        let parser = strict_parser();
+        assert_eq!(
+            parser.parse(args(&["program_name", "--output"])),
+            Err(ArgError::MissingValue("output".to_string()))
+        );
+        assert_eq!(
+            parser.parse(args(&["program_name", "--output", "--verbose"])),
+            Err(ArgError::MissingValue("output".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_boolean_flag_with_value() {
+        assert_eq!(
+            strict_parser().parse(args(&["program_name", "--verbose=yes"])),
+            Err(ArgError::UnexpectedValue("verbose".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_value_flags_disambiguate_positionals() {
+        let parsed = strict_parser()
+            .parse(args(&["program_name", "--verbose", "input.rs", "--output", "out.txt"]))
+            .unwrap();
+        assert_eq!(parsed.flags, HashSet::from(["verbose".to_string()]));
+        assert_eq!(parsed.positional, vec!["input.rs"]);
+        assert_eq!(parsed.options["output"], "out.txt");
+    }
+
+    #[test]
+    fn test_greeting_template() {
+        let person = Person::new("Alice".to_string(), 25)
+            .with_greeting_template("Hey {name}, {age} and counting! {unknown}".to_string());
+        assert_eq!(person.greet(), "Hey Alice, 25 and counting! {unknown}");
+    }
+
+    #[test]
+    fn test_default_greeting_without_template() {
+        let person = Person::new("Alice".to_string(), 25);
+        assert!(person.greeting_template.is_none());
+        assert_eq!(person.greet(), "Hello, I'm Alice and I'm 25 years old");
+    }
+
+    #[test]
+    fn test_line_ending_normalization() {
+        let dir = temp_dir("line_endings");
+        let mixed = "one\r\ntwo\nthree\r\n";
+        let cases = [
+            (LineEnding::Lf, "one\ntwo\nthree\n"),
+            (LineEnding::CrLf, "one\r\ntwo\r\nthree\r\n"),
+            (LineEnding::Preserve, mixed),
+        ];
+
+        for (line_ending, expected) in cases {
+            let mut processor = FileProcessor::new(&dir).with_line_ending(line_ending);
+            processor.write_file("out.txt", mixed).unwrap();
+            assert_eq!(std::fs::read(dir.join("out.txt")).unwrap(), expected.as_bytes());
+            assert_eq!(processor.bytes_written, expected.len() as u64);
+        }
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn test_file_hash() {
+        let dir = temp_dir("hash");
+        std::fs::write(dir.join("abc.txt"), "abc").unwrap();
+        let processor = FileProcessor::new(&dir);
+
+        assert_eq!(
+            processor.file_hash("abc.txt").unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(processor.processed_files.is_empty());
+    }
+
+    #[test]
+    fn test_diff_identical_files() {
+        let dir = temp_dir("diff_identical");
+        std::fs::write(dir.join("a.txt"), "one\ntwo\n").unwrap();
+        std::fs::write(dir.join("b.txt"), "one\ntwo\n").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        let diff = processor.diff_files("a.txt", "b.txt").unwrap();
+        assert_eq!(
+            diff,
+            vec![DiffLine::Context("one".to_string()), DiffLine::Context("two".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_diff_added_line() {
+        let dir = temp_dir("diff_added");
+        std::fs::write(dir.join("a.txt"), "one\nthree\n").unwrap();
+        std::fs::write(dir.join("b.txt"), "one\ntwo\nthree\n").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        let diff = processor.diff_files("a.txt", "b.txt").unwrap();
+        assert_eq!(
+            diff,
+            vec![
+                DiffLine::Context("one".to_string()),
+                DiffLine::Added("two".to_string()),
+                DiffLine::Context("three".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_replaced_line() {
+        let dir = temp_dir("diff_replaced");
+        std::fs::write(dir.join("a.txt"), "a\nb\nc\nd\n").unwrap();
+        std::fs::write(dir.join("b.txt"), "a\nx\nc\nd\n").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        let diff = processor.diff_files("a.txt", "b.txt").unwrap();
+        assert_eq!(
+            diff,
+            vec![
+                DiffLine::Context("a".to_string()),
+                DiffLine::Removed("b".to_string()),
+                DiffLine::Added("x".to_string()),
+                DiffLine::Context("c".to_string()),
+                DiffLine::Context("d".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_rust() {
+        let processor = FileProcessor::new(".");
+        let source = "fn main(){\nlet x=1;\n        if x>0 {println!(\"positive\");}\n}\n";
+
+        match processor.format_rust(source) {
+            Ok(formatted) => assert_eq!(
+                formatted,
+                "fn main() {\n    let x = 1;\n    if x > 0 {\n        println!(\"positive\");\n    }\n}\n"
+            ),
+            Err(FormatError::ToolNotFound(_)) => eprintln!("skipping: rustfmt not installed"),
+            Err(err) => panic!("unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn test_format_rust_reports_parse_errors() {
+        let processor = FileProcessor::new(".");
+        match processor.format_rust("fn main( {") {
+            Err(FormatError::Failed { stderr }) => assert!(!stderr.is_empty()),
+            Err(FormatError::ToolNotFound(_)) => eprintln!("skipping: rustfmt not installed"),
+            other => panic!("expected a formatting failure, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_backup_on_overwrite() {
+        let dir = temp_dir("backup");
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/main.rs"), "original").unwrap();
+        let mut processor = FileProcessor::new(&dir).with_backup(true);
+
+        processor.write_file("src/main.rs", "updated").unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join("src/main.rs")).unwrap(), "updated");
+        assert_eq!(std::fs::read_to_string(dir.join("src/main.rs.bak")).unwrap(), "original");
+        assert_eq!(processor.processed_files, vec!["src/main.rs.bak", "src/main.rs"]);
+
+        // A second overwrite must not clobber the first backup
+        processor.write_file("src/main.rs", "again").unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join("src/main.rs.bak")).unwrap(), "original");
+        assert_eq!(std::fs::read_to_string(dir.join(&processor.processed_files[2])).unwrap(), "updated");
+    }
+
+    #[test]
+    fn test_no_backup_for_new_file() {
+        let dir = temp_dir("backup_new");
+        let mut processor = FileProcessor::new(&dir).with_backup(true);
+
+        processor.write_file("new.txt", "content").unwrap();
+        assert!(!dir.join("new.txt.bak").exists());
+        assert_eq!(processor.processed_files, vec!["new.txt"]);
+    }
+
+    #[test]
+    fn test_dry_run_plans_writes_without_touching_disk() {
+        let dir = temp_dir("dry_run");
+        std::fs::write(dir.join("existing.txt"), "keep me").unwrap();
+        let mut processor = FileProcessor::new(&dir).with_dry_run(true);
+
+        processor.write_file("new.txt", "hello").unwrap();
+        processor.write_bytes("data.bin", &[1, 2, 3]).unwrap();
+        processor.write_file("existing.txt", "replaced").unwrap();
+
+        assert!(!dir.join("new.txt").exists());
+        assert!(!dir.join("data.bin").exists());
+        assert_eq!(std::fs::read_to_string(dir.join("existing.txt")).unwrap(), "keep me");
+        assert_eq!(
+            processor.planned_writes(),
+            &[
+                ("new.txt".to_string(), 5),
+                ("data.bin".to_string(), 3),
+                ("existing.txt".to_string(), 8),
+            ]
+        );
+
+        // Reads still go to disk
+        assert_eq!(processor.read_file("existing.txt").unwrap(), "keep me");
+        assert_eq!(processor.processed_files, vec!["existing.txt"]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_read_files_parallel() {
+        let dir = temp_dir("parallel");
+        let names: Vec<String> = (0..8).map(|i| format!("file{}.txt", i)).collect();
+        for (i, name) in names.iter().enumerate() {
+            std::fs::write(dir.join(name), format!("content {}", i)).unwrap();
+        }
+        let mut processor = FileProcessor::new(&dir);
+
+        let mut requested = names.clone();
+        requested.insert(3, "missing.txt".to_string());
+        let results = processor.read_files_parallel(requested.clone());
+
+        assert_eq!(results.len(), 9);
+        for ((name, content), expected) in results.iter().zip(&requested) {
+            assert_eq!(name, expected);
+            match name.as_str() {
+                "missing.txt" => assert!(matches!(content, Err(FileProcessorError::NotFound(_)))),
+                _ => assert_eq!(content.as_ref().unwrap(), &format!("content {}", &name[4..5])),
+            }
+        }
+        assert_eq!(processor.processed_files, names);
+        assert_eq!(processor.get_stats()["processed_files_count"], serde_json::json!(8));
+    }
+
+    #[test]
+    fn test_nested_write_without_create_parents() {
+        let dir = temp_dir("parents_off");
+        let mut processor = FileProcessor::new(&dir);
+
+        let err = processor.write_file("a/b/c/file.txt", "content").unwrap_err();
+        assert!(matches!(err, FileProcessorError::ParentMissing(path) if path.ends_with("a/b/c")));
+        assert!(!dir.join("a").exists());
+    }
+
+    #[test]
+    fn test_nested_write_with_create_parents() {
+        let dir = temp_dir("parents_on");
+        let mut processor = FileProcessor::new(&dir).with_create_parents(true);
+
+        processor.write_file("a/b/c/file.txt", "content").unwrap();
+        processor.write_file_atomic("x/y/atomic.txt", "atomic").unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join("a/b/c/file.txt")).unwrap(), "content");
+        assert_eq!(std::fs::read_to_string(dir.join("x/y/atomic.txt")).unwrap(), "atomic");
+    }
+
+    #[test]
+    fn test_copy_file_keeps_source() {
+        let dir = temp_dir("copy");
+        std::fs::write(dir.join("source.txt"), "copy me").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        assert_eq!(processor.copy_file("source.txt", "copy.txt").unwrap(), 7);
+        assert_eq!(std::fs::read_to_string(dir.join("source.txt")).unwrap(), "copy me");
+        assert_eq!(std::fs::read_to_string(dir.join("copy.txt")).unwrap(), "copy me");
+        assert_eq!(processor.processed_files, vec!["copy.txt"]);
+        assert!(matches!(
+            processor.copy_file("source.txt", "../escape.txt"),
+            Err(FileProcessorError::OutsideBasePath(_))
+        ));
+    }
+
+    #[test]
+    fn test_move_file_removes_source() {
+        let dir = temp_dir("move");
+        std::fs::write(dir.join("source.txt"), "move me").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        processor.move_file("source.txt", "moved.txt").unwrap();
+        assert!(!dir.join("source.txt").exists());
+        assert_eq!(std::fs::read_to_string(dir.join("moved.txt")).unwrap(), "move me");
+        assert_eq!(processor.processed_files, vec!["moved.txt"]);
+        assert!(matches!(
+            processor.move_file("source.txt", "again.txt"),
+            Err(FileProcessorError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_copy_and_move_in_dry_run() {
+        let dir = temp_dir("copy_move_dry_run");
+        std::fs::write(dir.join("source.txt"), "copy me").unwrap();
+        let mut processor = FileProcessor::new(&dir).with_dry_run(true);
+
+        assert_eq!(processor.copy_file("source.txt", "copy.txt").unwrap(), 7);
+        processor.move_file("source.txt", "moved.txt").unwrap();
+        assert!(!dir.join("copy.txt").exists());
+        assert!(!dir.join("moved.txt").exists());
+        assert_eq!(std::fs::read_to_string(dir.join("source.txt")).unwrap(), "copy me");
+        assert_eq!(
+            processor.planned_writes(),
+            [("copy.txt".to_string(), 7), ("moved.txt".to_string(), 7)]
+        );
+        assert_eq!(processor.planned_deletes(), ["source.txt"]);
+        assert!(processor.processed_files.is_empty());
+        assert!(matches!(
+            processor.copy_file("missing.txt", "copy.txt"),
+            Err(FileProcessorError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_copy_and_move_back_up_destination() {
+        let dir = temp_dir("copy_move_backup");
+        std::fs::write(dir.join("a.txt"), "new a").unwrap();
+        std::fs::write(dir.join("b.txt"), "new b").unwrap();
+        std::fs::write(dir.join("copy.txt"), "old copy").unwrap();
+        std::fs::write(dir.join("moved.txt"), "old moved").unwrap();
+        let mut processor = FileProcessor::new(&dir).with_backup(true);
+
+        processor.copy_file("a.txt", "copy.txt").unwrap();
+        processor.move_file("b.txt", "moved.txt").unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join("copy.txt")).unwrap(), "new a");
+        assert_eq!(std::fs::read_to_string(dir.join("copy.txt.bak")).unwrap(), "old copy");
+        assert_eq!(std::fs::read_to_string(dir.join("moved.txt")).unwrap(), "new b");
+        assert_eq!(std::fs::read_to_string(dir.join("moved.txt.bak")).unwrap(), "old moved");
+        assert_eq!(
+            processor.processed_files,
+            vec!["copy.txt.bak", "copy.txt", "moved.txt.bak", "moved.txt"]
+        );
+    }
+
+    #[test]
+    fn test_recorded_paths_are_normalized() {
+        let dir = temp_dir("normalize");
+        std::fs::create_dir_all(dir.join("a")).unwrap();
+        std::fs::write(dir.join("a/b.rs"), "").unwrap();
+        let mut processor = FileProcessor::new(&dir).with_dedup_processed(true);
+
+        processor.read_file("./a/b.rs").unwrap();
+        processor.read_file("a//b.rs").unwrap();
+        processor.read_file("a/./b.rs").unwrap();
+        assert_eq!(processor.processed_files, vec!["a/b.rs"]);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_recorded_windows_paths_are_normalized() {
+        assert_eq!(slash_path(Path::new(r".\a\\b.rs")), "a/b.rs");
+    }
+
+    #[test]
+    fn test_append_file() {
+        let dir = temp_dir("append");
+        let mut processor = FileProcessor::new(&dir);
+
+        for line in ["first\n", "second\n", "third\n"] {
+            processor.append_file("log.txt", line).unwrap();
+        }
+        assert_eq!(
+            std::fs::read_to_string(dir.join("log.txt")).unwrap(),
+            "first\nsecond\nthird\n"
+        );
+        assert_eq!(processor.get_stats()["bytes_written"], serde_json::json!(19));
+        assert_eq!(processor.processed_files, vec!["log.txt", "log.txt", "log.txt"]);
+    }
+
+    #[test]
+    fn test_typed_stats_match_map() {
+        let dir = temp_dir("typed_stats");
+        let mut processor = FileProcessor::new(&dir);
+        processor.write_file("a.txt", "abc").unwrap();
+        processor.read_file("a.txt").unwrap();
+
+        let stats = processor.stats();
+        assert_eq!(stats.processed_files_count, 2);
+        assert_eq!(stats.files, vec!["a.txt", "a.txt"]);
+        assert_eq!((stats.bytes_read, stats.bytes_written), (3, 3));
+
+        let map = processor.get_stats();
+        assert_eq!(map["base_path"], serde_json::json!(stats.base_path));
+        assert_eq!(map["processed_files_count"], serde_json::json!(stats.processed_files_count));
+        assert_eq!(map["files"], serde_json::json!(stats.files));
+        assert_eq!(map["bytes_read"], serde_json::json!(stats.bytes_read));
+        assert_eq!(map["bytes_written"], serde_json::json!(stats.bytes_written));
+        assert_eq!(map["by_language"], serde_json::json!(stats.by_language));
+        assert_eq!(map.len(), 6);
+    }
+
+    #[test]
+    fn test_read_latin1_file() {
+        let dir = temp_dir("latin1");
+        std::fs::write(dir.join("legacy.txt"), b"caf\xe9 cr\xe8me").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        assert!(processor.read_file("legacy.txt").is_err());
+        assert_eq!(
+            processor.read_file_with_encoding("legacy.txt", Encoding::Latin1).unwrap(),
+            "café crème"
+        );
+        assert_eq!(processor.processed_files, vec!["legacy.txt"]);
+    }
+
+    #[test]
+    fn test_read_utf16_file() {
+        let dir = temp_dir("utf16");
+        let le: Vec<u8> = "hé".encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        let be: Vec<u8> = "hé".encode_utf16().flat_map(|u| u.to_be_bytes()).collect();
+        std::fs::write(dir.join("le.txt"), le).unwrap();
+        std::fs::write(dir.join("be.txt"), be).unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        assert_eq!(processor.read_file_with_encoding("le.txt", Encoding::Utf16Le).unwrap(), "hé");
+        assert_eq!(processor.read_file_with_encoding("be.txt", Encoding::Utf16Be).unwrap(), "hé");
+    }
+
+    #[test]
+    fn test_decode_errors_unless_lossy() {
+        let dir = temp_dir("decode_error");
+        std::fs::write(dir.join("legacy.txt"), b"caf\xe9").unwrap();
+
+        let mut strict = FileProcessor::new(&dir);
+        let err = strict.read_file_with_encoding("legacy.txt", Encoding::Utf8).unwrap_err();
+        assert!(matches!(err, FileProcessorError::Decode { encoding: "UTF-8", .. }));
+
+        let mut lossy = FileProcessor::new(&dir).with_lossy_decoding(true);
+        assert_eq!(
+            lossy.read_file_with_encoding("legacy.txt", Encoding::Utf8).unwrap(),
+            "caf\u{fffd}"
+        );
+    }
+
+    #[test]
+    fn test_is_probably_binary() {
+        let dir = temp_dir("binary_detect");
+        std::fs::write(dir.join("text.rs"), "fn main() {\n\tprintln!(\"hé\");\r\n}\n").unwrap();
+        std::fs::write(dir.join("nul.bin"), b"abc\0def").unwrap();
+        std::fs::write(dir.join("control.bin"), [1u8, 2, 3, 4, b'a', 5, 6]).unwrap();
+        let processor = FileProcessor::new(&dir);
+
+        assert!(!processor.is_probably_binary("text.rs").unwrap());
+        assert!(processor.is_probably_binary("nul.bin").unwrap());
+        assert!(processor.is_probably_binary("control.bin").unwrap());
+    }
+
+    #[test]
+    fn test_process_dir_skips_binary_files() {
+        let dir = temp_dir("process_dir_binary");
+        std::fs::create_dir_all(dir.join("project")).unwrap();
+        std::fs::write(dir.join("project/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.join("project/blob.rs"), [0xffu8, 0x00, 0xfe, 0x00]).unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        let processed = processor.process_dir("project", "*.rs").unwrap();
+        assert_eq!(processed, vec!["project/main.rs"]);
+        assert_eq!(processor.skipped_files(), ["project/blob.rs"]);
+        assert_eq!(processor.processed_files, vec!["project/main.rs"]);
+    }
+
+    #[test]
+    fn test_set_base_path_switches_root() {
+        let first = temp_dir("base_first");
+        let second = temp_dir("base_second");
+        std::fs::write(first.join("a.txt"), "first").unwrap();
+        std::fs::write(second.join("a.txt"), "second").unwrap();
+        let mut processor = FileProcessor::new(&first);
+
+        assert_eq!(processor.read_file("a.txt").unwrap(), "first");
+        processor.set_base_path(&second).unwrap();
+        assert_eq!(processor.read_file("a.txt").unwrap(), "second");
+        assert_eq!(processor.processed_files, vec!["a.txt", "a.txt"]);
+        assert_eq!(processor.stats().base_path, second.to_string_lossy());
+    }
+
+    #[test]
+    fn test_set_base_path_rejects_invalid_paths() {
+        let dir = temp_dir("base_invalid");
+        std::fs::write(dir.join("file.txt"), "").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        let err = processor.set_base_path(dir.join("missing")).unwrap_err();
+        assert!(matches!(err, FileProcessorError::NotFound(_)));
+        let err = processor.set_base_path(dir.join("file.txt")).unwrap_err();
+        assert!(matches!(err, FileProcessorError::NotADirectory(_)));
+        assert_eq!(processor.stats().base_path, dir.to_string_lossy());
+    }
+
+    #[test]
+    fn test_process_dir_honors_ignore_patterns() {
+        let dir = temp_dir("process_dir_ignore");
+        std::fs::create_dir_all(dir.join("target/debug")).unwrap();
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(dir.join("web/dist")).unwrap();
+        std::fs::write(dir.join("target/debug/build.rs"), "").unwrap();
+        std::fs::write(dir.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.join("web/app.js"), "run()").unwrap();
+        std::fs::write(dir.join("web/dist/app.min.js"), "run()").unwrap();
+        let mut processor = FileProcessor::new(&dir)
+            .with_ignore_patterns(vec!["target/".to_string(), "*.min.js".to_string()]);
+
+        let processed = processor.process_dir(".", "**/*").unwrap();
+        assert_eq!(processed, vec!["src/main.rs", "web/app.js"]);
+    }
+
+    #[cfg(feature = "ignore")]
+    #[test]
+    fn test_process_dir_gitignore_skips_ignored_files() {
+        let dir = temp_dir("gitignore_repo");
+        std::fs::create_dir_all(dir.join("repo/.git")).unwrap();
+        std::fs::create_dir_all(dir.join("repo/src/logs")).unwrap();
+        std::fs::write(dir.join("repo/.gitignore"), "secret.txt\n").unwrap();
+        std::fs::write(dir.join("repo/src/.gitignore"), "*.log\n").unwrap();
+        std::fs::write(dir.join("repo/secret.txt"), "hunter2").unwrap();
+        std::fs::write(dir.join("repo/src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.join("repo/src/logs/run.log"), "log").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        let processed = processor.process_dir_gitignore("repo").unwrap();
+        assert_eq!(processed, vec!["repo/.gitignore", "repo/src/.gitignore", "repo/src/main.rs"]);
+    }
+
+    #[cfg(feature = "ignore")]
+    #[test]
+    fn test_process_dir_gitignore_outside_git() {
+        let dir = temp_dir("gitignore_no_repo");
+        std::fs::write(dir.join(".gitignore"), "secret.txt\n").unwrap();
+        std::fs::write(dir.join("secret.txt"), "hunter2").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        let processed = processor.process_dir_gitignore(".").unwrap();
+        assert_eq!(processed, vec![".gitignore", "secret.txt"]);
+    }
+
+    struct FormalGreeter;
+
+    impl Greeter for FormalGreeter {
+        fn greet(&self) -> String {
+            "Good day".to_string()
+        }
+
+        fn greet_person(&self, person: &Person) -> String {
+            format!("{}, {}.", self.greet(), person.name)
+        }
+    }
+
+    struct LocaleGreeter(&'static str);
+
+    impl Greeter for LocaleGreeter {
+        fn greet(&self) -> String {
+            format!("[{}]", self.0)
+        }
+
+        fn greet_person(&self, person: &Person) -> String {
+            person.greet_in(self.0)
+        }
+    }
+
+    #[test]
+    fn test_greeter_registry_styles() {
+        let mut registry = GreeterRegistry::new();
+        registry.register("formal", Box::new(FormalGreeter));
+        registry.register("spanish", Box::new(LocaleGreeter("es")));
+        let person = Person::new("Alice".to_string(), 30);
+
+        assert_eq!(registry.greet_with("formal", &person).unwrap(), "Good day, Alice.");
+        assert_eq!(
+            registry.greet_with("spanish", &person).unwrap(),
+            "Hola, soy Alice y tengo 30 años"
+        );
+        assert_eq!(registry.greet_with("pirate", &person), None);
+    }
+
+    #[test]
+    fn test_greet_person_defaults_to_own_greeting() {
+        let mut registry = GreeterRegistry::new();
+        let host = Person::new("Host".to_string(), 40);
+        registry.register("person", Box::new(host));
+        let guest = Person::new("Bob".to_string(), 25).with_greeting_template("Hi, {name}!".to_string());
+
+        assert_eq!(registry.greet_with("person", &guest).unwrap(), "Hi, Bob!");
+    }
+
+    #[cfg(feature = "notify")]
+    #[test]
+    fn test_watch_reports_modified_source_file() {
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let dir = temp_dir("watch");
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.join("src/notes.txt"), "").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            processor
+                .watch("src", |path| sender.send(path.to_path_buf()).unwrap())
+                .unwrap();
+        });
+
+        // Give the watcher time to register before writing
+        std::thread::sleep(Duration::from_millis(300));
+        std::fs::write(dir.join("src/notes.txt"), "ignored").unwrap();
+        std::fs::write(dir.join("src/main.rs"), "fn main() { run(); }").unwrap();
+        std::fs::write(dir.join("src/main.rs"), "fn main() { run(); run(); }").unwrap();
+
+        let changed = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(changed, Path::new("src/main.rs"));
+        assert!(receiver.recv_timeout(Duration::from_millis(500)).is_err());
+    }
+
+    fn numbered_file(name: &str, lines: usize) -> PathBuf {
+        let dir = temp_dir(name);
+        let content: String = (1..=lines).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(dir.join("numbered.txt"), content).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_read_line_range_mid_file() {
+        let dir = numbered_file("line_range_mid", 50);
+        let mut processor = FileProcessor::new(&dir);
+        assert_eq!(
+            processor.read_line_range("numbered.txt", 40, 44).unwrap(),
+            vec!["line 40", "line 41", "line 42", "line 43", "line 44"]
+        );
+        assert_eq!(processor.read_line_range("numbered.txt", 7, 7).unwrap(), vec!["line 7"]);
+    }
+
+    #[test]
+    fn test_read_line_range_past_eof() {
+        let dir = numbered_file("line_range_eof", 5);
+        let mut processor = FileProcessor::new(&dir);
+        assert_eq!(processor.read_line_range("numbered.txt", 4, 10).unwrap(), vec!["line 4", "line 5"]);
+        assert!(processor.read_line_range("numbered.txt", 8, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_read_line_range_invalid() {
+        let dir = numbered_file("line_range_invalid", 5);
+        let mut processor = FileProcessor::new(&dir);
+        assert!(matches!(
+            processor.read_line_range("numbered.txt", 0, 3),
+            Err(FileProcessorError::InvalidLineRange { start: 0, end: 3 })
+        ));
+        assert!(matches!(
+            processor.read_line_range("numbered.txt", 4, 2),
+            Err(FileProcessorError::InvalidLineRange { start: 4, end: 2 })
+        ));
+        assert!(processor.processed_files.is_empty());
+    }
+
+    #[cfg(feature = "fs2")]
+    #[test]
+    fn test_locked_writes_never_interleave() {
+        let dir = temp_dir("locking");
+        let writers: Vec<_> = [b'a', b'b']
+            .into_iter()
+            .map(|byte| {
+                let dir = dir.clone();
+                std::thread::spawn(move || {
+                    let content = String::from_utf8(vec![byte; 256 * 1024]).unwrap();
+                    let mut processor = FileProcessor::new(&dir).with_locking(true);
+                    for _ in 0..20 {
+                        processor.write_file("shared.txt", &content).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let content = std::fs::read(dir.join("shared.txt")).unwrap();
+        assert_eq!(content.len(), 256 * 1024);
+        assert!(content.iter().all(|&b| b == content[0]));
+    }
+
+    #[cfg(feature = "fs2")]
+    #[test]
+    fn test_every_writer_takes_the_lock() {
+        let dir = temp_dir("locking_writers");
+        std::fs::write(dir.join("crlf.txt"), "a\r\nb\r\n").unwrap();
+        let mut processor = FileProcessor::new(&dir).with_locking(true);
+
+        processor.write_bytes("data.bin", &[1, 2, 3]).unwrap();
+        processor.append_file("log.txt", "line\n").unwrap();
+        processor.normalize_file("crlf.txt", Encoding::Utf8, LineEnding::Lf).unwrap();
+        for name in ["data.bin", "log.txt", "crlf.txt"] {
+            assert!(dir.join(format!(".{}.lock", name)).is_file(), "{} was written unlocked", name);
+        }
+    }
+
+    #[test]
+    fn test_truncate_file() {
+        let dir = temp_dir("truncate");
+        std::fs::write(dir.join("generated.rs"), "old content").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        processor.truncate_file("generated.rs").unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join("generated.rs")).unwrap(), "");
+        assert_eq!(processor.processed_files, vec!["generated.rs"]);
+        assert_eq!(processor.stats().bytes_written, 0);
+
+        assert!(processor.truncate_file("out/new.rs").is_err());
+        let mut processor = processor.with_create_parents(true);
+        processor.truncate_file("out/new.rs").unwrap();
+        assert!(dir.join("out/new.rs").is_file());
+        assert!(matches!(
+            processor.truncate_file("../escape.rs"),
+            Err(FileProcessorError::OutsideBasePath(_))
+        ));
+    }
+
+    #[test]
+    fn test_stats_by_language() {
+        let dir = temp_dir("by_language");
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        std::fs::write(dir.join("main.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.join("lib.rs"), "").unwrap();
+        std::fs::write(dir.join("docs/guide.md"), "# Guide").unwrap();
+        std::fs::write(dir.join("run"), "#!/usr/bin/env python3\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        processor.process_dir(".", "**/*").unwrap();
+        let stats = processor.stats();
+        assert_eq!(
+            stats.by_language,
+            HashMap::from([
+                ("Rust".to_string(), 2),
+                ("Markdown".to_string(), 1),
+                ("Python".to_string(), 1),
+                ("Unknown".to_string(), 1),
+            ])
+        );
+        assert_eq!(stats.by_language.values().sum::<usize>(), stats.processed_files_count);
+        assert_eq!(processor.get_stats()["by_language"]["Rust"], 2);
+
+        processor.reset();
+        assert!(processor.stats().by_language.is_empty());
+    }
+
+    #[test]
+    fn test_delete_existing_file() {
+        let dir = temp_dir("delete");
+        std::fs::create_dir_all(dir.join("gen")).unwrap();
+        std::fs::write(dir.join("gen/out.rs"), "generated").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        processor.delete_file("gen/out.rs").unwrap();
+        assert!(!dir.join("gen/out.rs").exists());
+        assert_eq!(processor.deleted_files(), ["gen/out.rs"]);
+    }
+
+    #[test]
+    fn test_delete_in_dry_run_is_planned() {
+        let dir = temp_dir("delete_dry_run");
+        std::fs::write(dir.join("out.rs"), "generated").unwrap();
+        let mut processor = FileProcessor::new(&dir).with_dry_run(true);
+
+        processor.delete_file("out.rs").unwrap();
+        assert!(dir.join("out.rs").exists());
+        assert_eq!(processor.planned_deletes(), ["out.rs"]);
+        assert!(processor.deleted_files().is_empty());
+        assert!(matches!(
+            processor.delete_file("missing.rs"),
+            Err(FileProcessorError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_delete_with_backup() {
+        let dir = temp_dir("delete_backup");
+        std::fs::write(dir.join("out.rs"), "generated").unwrap();
+        let mut processor = FileProcessor::new(&dir).with_backup(true);
+
+        processor.delete_file("out.rs").unwrap();
+        assert!(!dir.join("out.rs").exists());
+        assert_eq!(std::fs::read_to_string(dir.join("out.rs.bak")).unwrap(), "generated");
+        assert_eq!(processor.processed_files, vec!["out.rs.bak"]);
+        assert_eq!(processor.deleted_files(), ["out.rs"]);
+    }
+
+    #[test]
+    fn test_delete_missing_file() {
+        let dir = temp_dir("delete_missing");
+        let mut processor = FileProcessor::new(&dir);
+        assert!(matches!(
+            processor.delete_file("missing.rs"),
+            Err(FileProcessorError::NotFound(_))
+        ));
+        assert!(processor.deleted_files().is_empty());
+    }
+
+    #[test]
+    fn test_delete_rejects_escape() {
+        let dir = temp_dir("delete_escape");
+        std::fs::create_dir_all(dir.join("base")).unwrap();
+        std::fs::write(dir.join("keep.rs"), "").unwrap();
+        let mut processor = FileProcessor::new(dir.join("base"));
+
+        assert!(matches!(
+            processor.delete_file("../keep.rs"),
+            Err(FileProcessorError::OutsideBasePath(_))
+        ));
+        assert!(dir.join("keep.rs").exists());
+    }
+
+    #[test]
+    fn test_exists_is_file_is_dir() {
+        let dir = temp_dir("exists");
+        std::fs::create_dir_all(dir.join("base/src")).unwrap();
+        std::fs::write(dir.join("base/src/main.rs"), "").unwrap();
+        std::fs::write(dir.join("outside.rs"), "").unwrap();
+        let processor = FileProcessor::new(dir.join("base"));
+
+        assert!(processor.exists("src/main.rs"));
+        assert!(processor.is_file("src/main.rs"));
+        assert!(!processor.is_dir("src/main.rs"));
+
+        assert!(processor.exists("src"));
+        assert!(processor.is_dir("src"));
+        assert!(!processor.is_file("src"));
+
+        assert!(!processor.exists("src/missing.rs"));
+        assert!(!processor.is_file("src/missing.rs"));
+
+        assert!(!processor.exists("../outside.rs"));
+        assert!(!processor.is_file("../outside.rs"));
+    }
+
+    #[test]
+    fn test_analyze_dir_on_fixture() {
+        let fixture = Path::new(file!()).parent().unwrap().join("fixtures");
+        let mut processor = FileProcessor::new(&fixture);
+
+        let reports = processor.analyze_dir("analyze_tree").unwrap();
+        let paths: Vec<_> = reports.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["analyze_tree/README.md", "analyze_tree/src/broken.rs", "analyze_tree/src/lib.rs"]
+        );
+
+        assert_eq!(
+            reports[2],
+            FileReport {
+                path: "analyze_tree/src/lib.rs".to_string(),
+                language: Language::Rust,
+                syntax_ok: Some(true),
+                markers: vec![MarkerHit {
+                    kind: "TODO".to_string(),
+                    position: position::Position::new(3, 8),
+                    text: "TODO: compute this properly".to_string(),
+                }],
+                lines: LineCount { total: 5, code: 3, comment: 2, blank: 0 },
+            }
+        );
+        assert_eq!(reports[1].syntax_ok, Some(false));
+        assert_eq!(reports[1].markers.len(), 1);
+
+        let readme = &reports[0];
+        assert_eq!((readme.language, readme.syntax_ok), (Language::Markdown, None));
+        assert!(readme.markers.is_empty());
+        assert_eq!(readme.lines, LineCount { total: 4, code: 2, comment: 1, blank: 1 });
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_analyze_dir_parallel_matches_sequential() {
+        let fixture = Path::new(file!()).parent().unwrap().join("fixtures");
+        let mut sequential = FileProcessor::new(&fixture);
+        let expected = sequential.analyze_dir("analyze_tree").unwrap();
+
+        for max_threads in [0, 1, 4] {
+            let mut parallel = FileProcessor::new(&fixture);
+            assert_eq!(parallel.analyze_dir_parallel("analyze_tree", max_threads).unwrap(), expected);
+            assert_eq!(parallel.processed_files, sequential.processed_files);
+            assert_eq!(parallel.skipped_files(), sequential.skipped_files());
+            assert_eq!(parallel.get_stats(), sequential.get_stats());
+        }
+    }
+
+    #[test]
+    fn test_apply_edit_replaces_span() {
+        let dir = temp_dir("apply_edit");
+        std::fs::write(dir.join("lib.rs"), "fn old_name() {}\nfn other() {}\n").unwrap();
+        let mut processor = FileProcessor::new(&dir).with_atomic_writes(true);
+
+        processor.apply_edit("lib.rs", 3, 11, "new_name").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("lib.rs")).unwrap(),
+            "fn new_name() {}\nfn other() {}\n"
+        );
+        assert_eq!(processor.processed_files, vec!["lib.rs"]);
+    }
+
+    #[test]
+    fn test_apply_edit_rejects_invalid_offsets() {
+        let dir = temp_dir("apply_edit_invalid");
+        std::fs::write(dir.join("text.txt"), "café").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        assert!(matches!(
+            processor.apply_edit("text.txt", 4, 5, "e"),
+            Err(FileProcessorError::NotCharBoundary(4))
+        ));
+        assert!(matches!(
+            processor.apply_edit("text.txt", 2, 10, ""),
+            Err(FileProcessorError::InvalidSpan { start: 2, end: 10, len: 5 })
+        ));
+        assert!(matches!(
+            processor.apply_edit("text.txt", 3, 1, ""),
+            Err(FileProcessorError::InvalidSpan { .. })
+        ));
+        assert_eq!(std::fs::read_to_string(dir.join("text.txt")).unwrap(), "café");
+    }
+
+    #[test]
+    fn test_merge_stats() {
+        let dir = temp_dir("merge_stats");
+        std::fs::write(dir.join("a.rs"), "abc").unwrap();
+        std::fs::write(dir.join("b.md"), "hello").unwrap();
+        let mut first = FileProcessor::new(&dir);
+        first.read_file("a.rs").unwrap();
+        let mut second = FileProcessor::new(&dir);
+        second.read_file("a.rs").unwrap();
+        second.read_file("b.md").unwrap();
+        second.write_file("c.rs", "x").unwrap();
+
+        let mut merged = first.stats();
+        merged.merge(&second.stats());
+        assert_eq!(merged.base_path, dir.to_string_lossy());
+        assert_eq!(merged.processed_files_count, 4);
+        assert_eq!(merged.files, vec!["a.rs", "b.md", "c.rs"]);
+        assert_eq!((merged.bytes_read, merged.bytes_written), (11, 1));
+        assert_eq!(merged.by_language["Rust"], 3);
+        assert_eq!(merged.by_language["Markdown"], 1);
+
+        merged.merge(&FileProcessor::new("elsewhere").stats());
+        assert_eq!(merged.base_path, MULTIPLE_BASE_PATHS);
+    }
+
+    #[test]
+    fn test_analyze_source_matches_on_disk() {
+        let fixture = Path::new(file!()).parent().unwrap().join("fixtures");
+        let mut processor = FileProcessor::new(&fixture);
+        let on_disk = processor
+            .analyze_dir("analyze_tree/src")
+            .unwrap()
+            .into_iter()
+            .find(|r| r.path.ends_with("lib.rs"))
+            .unwrap();
+
+        let source = std::fs::read_to_string(fixture.join("analyze_tree/src/lib.rs")).unwrap();
+        let processed = processor.processed_files.len();
+        let in_memory = processor.analyze_source(&source, Language::Rust);
+        assert_eq!(in_memory.path, STDIN_PATH);
+        assert_eq!(in_memory, FileReport { path: in_memory.path.clone(), ..on_disk });
+        assert_eq!(processor.processed_files.len(), processed);
+    }
+
+    fn three_level_tree(name: &str) -> PathBuf {
+        let dir = temp_dir(name);
+        std::fs::create_dir_all(dir.join("top/mid/deep")).unwrap();
+        std::fs::write(dir.join("top/a.rs"), "").unwrap();
+        std::fs::write(dir.join("top/mid/b.rs"), "").unwrap();
+        std::fs::write(dir.join("top/mid/deep/c.rs"), "").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_process_dir_max_depth() {
+        let dir = three_level_tree("max_depth");
+
+        let mut processor = FileProcessor::new(&dir).with_max_depth(1);
+        assert_eq!(processor.process_dir("top", "*.rs").unwrap(), vec!["top/a.rs", "top/mid/b.rs"]);
+
+        let mut processor = FileProcessor::new(&dir).with_max_depth(0);
+        assert_eq!(processor.process_dir("top", "*.rs").unwrap(), vec!["top/a.rs"]);
+
+        let mut processor = FileProcessor::new(&dir);
+        assert_eq!(processor.process_dir("top", "*.rs").unwrap().len(), 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_process_dir_symlink_loop() {
+        let dir = three_level_tree("symlink_loop");
+        std::os::unix::fs::symlink("..", dir.join("top/mid/deep/loop")).unwrap();
+        std::os::unix::fs::symlink(dir.join("top/mid/b.rs"), dir.join("top/link.rs")).unwrap();
+
+        let mut processor = FileProcessor::new(&dir);
+        let processed = processor.process_dir("top", "*.rs").unwrap();
+        assert_eq!(processed, vec!["top/a.rs", "top/mid/b.rs", "top/mid/deep/c.rs"]);
+
+        let mut processor = FileProcessor::new(&dir).with_follow_symlinks(true);
+        let processed = processor.process_dir("top", "*.rs").unwrap();
+        assert_eq!(
+            processed,
+            vec!["top/a.rs", "top/link.rs", "top/mid/b.rs", "top/mid/deep/c.rs"]
+        );
+    }
+
+    #[test]
+    fn test_progress_callback() {
+        use std::sync::{Arc, Mutex};
+
+        let dir = temp_dir("progress");
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        for name in ["a.rs", "b.rs", "c.txt"] {
+            std::fs::write(dir.join("src").join(name), "").unwrap();
+        }
+        std::fs::write(dir.join("src/blob.rs"), [0u8; 4]).unwrap();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&calls);
+        let mut processor = FileProcessor::new(&dir)
+            .with_progress(move |done, total| recorded.lock().unwrap().push((done, total)));
+
+        processor.process_dir("src", "*.rs").unwrap();
+        assert_eq!(*calls.lock().unwrap(), vec![(1, 3), (2, 3), (3, 3)]);
+
+        calls.lock().unwrap().clear();
+        processor.analyze_dir("src").unwrap();
+        assert_eq!(calls.lock().unwrap().last(), Some(&(4, 4)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_file_with_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_dir("file_mode");
+        let mut processor = FileProcessor::new(&dir);
+        processor.write_file_with_mode("run.sh", "#!/bin/sh\necho hi\n", 0o755).unwrap();
+
+        let mode = std::fs::metadata(dir.join("run.sh")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+        assert_eq!(std::fs::read_to_string(dir.join("run.sh")).unwrap(), "#!/bin/sh\necho hi\n");
+    }
+
+    #[test]
+    fn test_retry_transient_failures() {
+        let processor = FileProcessor::new(".").with_retries(3, Duration::from_millis(1));
+        let mut calls = 0;
+        let result = processor.retry(|| {
+            calls += 1;
+            if calls <= 2 {
+                Err(io::Error::from(io::ErrorKind::Interrupted))
+            } else {
+                Ok("written")
+            }
+        });
+        assert_eq!(result.unwrap(), "written");
+        assert_eq!(calls, 3);
+
+        let mut calls = 0;
+        let result: io::Result<()> = processor.retry(|| {
+            calls += 1;
+            Err(io::Error::from(io::ErrorKind::WouldBlock))
+        });
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::WouldBlock);
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_retry_fails_fast_on_permanent_errors() {
+        let processor = FileProcessor::new(".").with_retries(5, Duration::from_millis(1));
+        let mut calls = 0;
+        let result: io::Result<()> = processor.retry(|| {
+            calls += 1;
+            Err(io::Error::from(io::ErrorKind::PermissionDenied))
+        });
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_list_files_leaves_stats_unchanged() {
+        let dir = three_level_tree("list_files");
+        std::fs::create_dir_all(dir.join("top/target")).unwrap();
+        std::fs::write(dir.join("top/target/out.rs"), "").unwrap();
+        std::fs::write(dir.join("top/notes.txt"), "").unwrap();
+        let processor = FileProcessor::new(&dir)
+            .with_max_depth(1)
+            .with_ignore_patterns(vec!["target/".to_string()]);
+
+        let files = processor.list_files("top", "*.rs").unwrap();
+        assert_eq!(files, vec![PathBuf::from("top/a.rs"), PathBuf::from("top/mid/b.rs")]);
+        assert_eq!(processor.stats(), FileProcessor::new(&dir).stats());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_from_config_json() {
+        let dir = temp_dir("from_config");
+        std::fs::create_dir_all(dir.join("target")).unwrap();
+        std::fs::write(dir.join("target/out.rs"), "").unwrap();
+        std::fs::write(dir.join("small.rs"), "ok").unwrap();
+        std::fs::write(dir.join("large.txt"), "far too large").unwrap();
+
+        let config: FileProcessorConfig = serde_json::from_str(
+            r#"{"max_file_size": 4, "dry_run": true, "line_ending": "crlf", "ignore_patterns": ["target/"]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.line_ending, LineEnding::CrLf);
+        assert_eq!(config.write_attempts, 1);
+        let mut processor = FileProcessor::from_config(&dir, config);
+
+        assert!(matches!(
+            processor.read_file("large.txt"),
+            Err(FileProcessorError::FileTooLarge { limit: 4, .. })
+        ));
+        assert_eq!(processor.process_dir(".", "*.rs").unwrap(), vec!["small.rs"]);
+        processor.write_file("new.txt", "a\nb").unwrap();
+        assert!(!dir.join("new.txt").exists());
+        assert_eq!(processor.planned_writes(), [("new.txt".to_string(), 4)]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_config_round_trip() {
+        let config = FileProcessorConfig {
+            max_depth: Some(2),
+            watch_pattern: Some("**/*.rs".to_string()),
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(serde_json::from_str::<FileProcessorConfig>(&json).unwrap(), config);
+        assert_eq!(serde_json::from_str::<FileProcessorConfig>("{}").unwrap(), FileProcessorConfig::default());
+    }
+
+    /// Yields its chunks in order, failing where a chunk is `None`
+    struct FlakyReader(Vec<Option<&'static [u8]>>);
+
+    impl Read for FlakyReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Ok(0);
+            }
+            match self.0.remove(0) {
+                Some(chunk) => {
+                    buf[..chunk.len()].copy_from_slice(chunk);
+                    Ok(chunk.len())
+                }
+                None => Err(io::Error::other("disk hiccup")),
+            }
+        }
+    }
+
+    #[test]
+    fn test_read_chunks_continues_past_errors() {
+        let mut errors = Vec::new();
+        let reader = FlakyReader(vec![Some(b"hello "), None, Some(b"world")]);
+        assert_eq!(read_chunks(reader, &mut errors), b"hello world");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("disk hiccup"));
+
+        // A reader that keeps failing is eventually given up on
+        let mut errors = Vec::new();
+        let reader = FlakyReader(vec![Some(b"partial"), None, None, None, Some(b"never read")]);
+        assert_eq!(read_chunks(reader, &mut errors), b"partial");
+        assert_eq!(errors.len(), MAX_READ_ERRORS);
+    }
+
+    #[test]
+    fn test_read_file_lossy_records_file() {
+        let dir = temp_dir("read_lossy");
+        fs::write(dir.join("ok.txt"), b"caf\xe9").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        let (content, errors) = processor.read_file_lossy("ok.txt");
+        assert_eq!(content, "caf\u{FFFD}");
+        assert!(errors.is_empty());
+
+        let (content, errors) = processor.read_file_lossy("missing.txt");
+        assert!(content.is_empty());
+        assert!(matches!(errors[..], [FileProcessorError::NotFound(_)]));
+        assert_eq!(processor.stats().files, vec!["ok.txt", "missing.txt"]);
+    }
+
+    #[test]
+    fn test_team_greeting() {
+        let team = |names: &[&str]| Team(names.iter().map(|name| Person::new(name.to_string(), 30)).collect());
+        assert_eq!(team(&[]).greet(), "Hello from the team");
+        assert_eq!(team(&["Alice"]).greet(), "Hello from Alice");
+        assert_eq!(team(&["Alice", "Bob"]).greet(), "Hello from Alice and Bob");
+        assert_eq!(team(&["Alice", "Bob", "Carol"]).greet(), "Hello from Alice, Bob, and Carol");
+        assert_eq!(team(&["Alice", "Bob", "Carol"]).greet_in("fr"), "Hello from Alice, Bob, and Carol");
+    }
+
+    #[cfg(feature = "serde")]
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Settings {
+        name: String,
+        max_line_length: usize,
+        languages: Vec<String>,
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_read_config_json() {
+        let fixture = Path::new(file!()).parent().unwrap().join("fixtures/config");
+        let mut processor = FileProcessor::new(&fixture);
+        let settings: Settings = processor.read_config("settings.json").unwrap();
+        assert_eq!(settings.max_line_length, 100);
+        assert_eq!(processor.stats().files, vec!["settings.json"]);
+
+        match processor.read_config::<Settings, _>("invalid.json") {
+            Err(error::ConfigError::Parse { path, line, .. }) => {
+                assert_eq!(path, Path::new("invalid.json"));
+                assert_eq!(line, Some(3));
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        assert!(matches!(
+            processor.read_config::<Settings, _>("settings.yaml"),
+            Err(error::ConfigError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[cfg(all(feature = "serde", feature = "toml"))]
+    #[test]
+    fn test_read_config_toml_matches_json() {
+        let fixture = Path::new(file!()).parent().unwrap().join("fixtures/config");
+        let mut processor = FileProcessor::new(&fixture);
+        let from_json: Settings = processor.read_config("settings.json").unwrap();
+        let from_toml: Settings = processor.read_config("settings.toml").unwrap();
+        assert_eq!(from_json, from_toml);
+    }
+
+    #[test]
+    fn test_rename_file_in_same_dir() {
+        let dir = temp_dir("rename_same_dir");
+        fs::write(dir.join("old.rs"), "fn main() {}").unwrap();
+        let mut processor = FileProcessor::new(&dir).with_dedup_processed(true);
+        processor.read_file("old.rs").unwrap();
+
+        processor.rename_file("old.rs", "new.rs").unwrap();
+        assert!(!dir.join("old.rs").exists());
+        assert_eq!(fs::read_to_string(dir.join("new.rs")).unwrap(), "fn main() {}");
+        assert_eq!(processor.stats().files, vec!["new.rs"]);
+        assert!(matches!(
+            processor.rename_file("old.rs", "other.rs"),
+            Err(FileProcessorError::NotFound(_))
+        ));
+        assert!(matches!(
+            processor.rename_file("new.rs", "../escaped.rs"),
+            Err(FileProcessorError::OutsideBasePath(_))
+        ));
+    }
+
+    #[test]
+    fn test_rename_file_falls_back_to_copy_across_devices() {
+        let dir = temp_dir("rename_cross_device");
+        fs::write(dir.join("old.rs"), "fn main() {}").unwrap();
+        let mut processor = FileProcessor::new(&dir).with_create_parents(true);
+
+        let cross_device = |_: &Path, _: &Path| Err(io::Error::from(io::ErrorKind::CrossesDevices));
+        let remove = |path: &Path| fs::remove_file(path);
+        processor.rename_with(Path::new("old.rs"), Path::new("moved/new.rs"), cross_device, remove).unwrap();
+        assert!(!dir.join("old.rs").exists());
+        assert_eq!(fs::read_to_string(dir.join("moved/new.rs")).unwrap(), "fn main() {}");
+        assert_eq!(processor.stats().files, vec!["moved/new.rs"]);
+
+        let failing = |_: &Path, _: &Path| Err(io::Error::from(io::ErrorKind::PermissionDenied));
+        assert!(matches!(
+            processor.rename_with(Path::new("moved/new.rs"), Path::new("back.rs"), failing, remove),
+            Err(FileProcessorError::PermissionDenied(_))
+        ));
+        assert!(dir.join("moved/new.rs").exists());
+
+        // A source that can't be removed after the copy leaves only the original
+        let stuck = |_: &Path| Err(io::Error::from(io::ErrorKind::PermissionDenied));
+        assert!(matches!(
+            processor.rename_with(Path::new("moved/new.rs"), Path::new("back.rs"), cross_device, stuck),
+            Err(FileProcessorError::PermissionDenied(path)) if path.ends_with("moved/new.rs")
+        ));
+        assert!(dir.join("moved/new.rs").exists());
+        assert!(!dir.join("back.rs").exists());
+        assert_eq!(processor.stats().files, vec!["moved/new.rs"]);
+    }
+
+    #[test]
+    fn test_rename_file_in_dry_run() {
+        let dir = temp_dir("rename_dry_run");
+        fs::write(dir.join("old.rs"), "fn main() {}").unwrap();
+        let mut processor = FileProcessor::new(&dir).with_dry_run(true);
+
+        processor.rename_file("old.rs", "new.rs").unwrap();
+        assert!(dir.join("old.rs").exists());
+        assert!(!dir.join("new.rs").exists());
+        assert_eq!(processor.planned_writes(), [("new.rs".to_string(), 12)]);
+        assert_eq!(processor.planned_deletes(), ["old.rs"]);
+        assert!(processor.stats().files.is_empty());
+    }
+
+    #[test]
+    fn test_rename_file_backs_up_destination() {
+        let dir = temp_dir("rename_backup");
+        fs::write(dir.join("old.rs"), "fn main() {}").unwrap();
+        fs::write(dir.join("new.rs"), "fn old() {}").unwrap();
+        let mut processor = FileProcessor::new(&dir).with_backup(true);
+
+        processor.rename_file("old.rs", "new.rs").unwrap();
+        assert!(!dir.join("old.rs").exists());
+        assert_eq!(fs::read_to_string(dir.join("new.rs")).unwrap(), "fn main() {}");
+        assert_eq!(fs::read_to_string(dir.join("new.rs.bak")).unwrap(), "fn old() {}");
+        assert_eq!(processor.stats().files, vec!["new.rs.bak", "new.rs"]);
+    }
+
+    #[test]
+    fn test_relative_path_of_nested_file() {
+        let dir = temp_dir("relative_nested");
+        fs::create_dir_all(dir.join("src/bin")).unwrap();
+        fs::write(dir.join("src/bin/cli.rs"), "fn main() {}").unwrap();
+        let processor = FileProcessor::new(&dir);
+
+        let expected = Some(PathBuf::from("src/bin/cli.rs"));
+        assert_eq!(processor.relative_path(dir.join("src/bin/cli.rs")), expected);
+        assert_eq!(processor.relative_path("./src/../src/bin/cli.rs"), expected);
+        assert_eq!(processor.relative_path("src/new.rs"), Some(PathBuf::from("src/new.rs")));
+        assert_eq!(processor.relative_path(&dir), Some(PathBuf::new()));
+    }
+
+    #[test]
+    fn test_relative_path_outside_base() {
+        let dir = temp_dir("relative_outside");
+        let processor = FileProcessor::new(dir.join("project"));
+        fs::create_dir_all(dir.join("project")).unwrap();
+        fs::write(dir.join("secret.txt"), "x").unwrap();
+
+        assert_eq!(processor.relative_path(dir.join("secret.txt")), None);
+        assert_eq!(processor.relative_path("../secret.txt"), None);
+        assert_eq!(processor.relative_path(dir.join("project/../secret.txt")), None);
+        assert_eq!(processor.relative_path(dir.join("project/../missing.txt")), None);
+        assert_eq!(
+            processor.relative_path(dir.join("project/src/../missing.txt")),
+            Some(PathBuf::from("missing.txt"))
+        );
+    }
+
+    #[test]
+    fn test_convert_tabs_on_write() {
+        let dir = temp_dir("convert_tabs");
+        let mut processor = FileProcessor::new(&dir).with_convert_tabs(4);
+        let source = "fn main() {\n\tlet s = \"a\tb\";\n\tif true {\n\t\trun();\n \t}\n\tlet m = \"one\n\ttwo\";\n}\n";
+        processor.write_file("main.rs", source).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(dir.join("main.rs")).unwrap(),
+            "fn main() {\n    let s = \"a\tb\";\n    if true {\n        run();\n    }\n    let m = \"one\n\ttwo\";\n}\n"
+        );
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_grep_matches_across_lines() {
+        let dir = temp_dir("grep_matches");
+        fs::write(dir.join("lib.rs"), "let a = unwrap();\r\nlet é = x.unwrap();\nok();\nunwrap(); unwrap()").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        let matches = processor.grep("lib.rs", r"unwrap\(\)").unwrap();
+        let found: Vec<_> = matches.iter().map(|m| (m.line, m.column)).collect();
+        assert_eq!(found, vec![(1, 9), (2, 11), (4, 1), (4, 11)]);
+        assert!(matches.iter().all(|m| m.matched_text == "unwrap()"));
+        assert_eq!(processor.stats().files, vec!["lib.rs"]);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_grep_rejects_invalid_pattern() {
+        let dir = temp_dir("grep_invalid");
+        fs::write(dir.join("lib.rs"), "fn main() {}").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        match processor.grep("lib.rs", "fn (") {
+            Err(error::GrepError::BadPattern { pattern, .. }) => assert_eq!(pattern, "fn ("),
+            other => panic!("expected a bad pattern error, got {:?}", other),
+        }
+        assert!(processor.stats().files.is_empty());
+        assert!(matches!(
+            processor.grep("missing.rs", "fn"),
+            Err(error::GrepError::Read(FileProcessorError::NotFound(_)))
+        ));
+    }
+
+    #[test]
+    fn test_common_prefix_of_shared_directory() {
+        let dir = temp_dir("common_prefix_shared");
+        fs::create_dir_all(dir.join("src/bin")).unwrap();
+        fs::create_dir_all(dir.join("src/util")).unwrap();
+        let mut processor = FileProcessor::new(&dir);
+        assert_eq!(processor.common_prefix(), None);
+
+        processor.write_file("src/bin/cli.rs", "").unwrap();
+        assert_eq!(processor.common_prefix(), Some(PathBuf::from("src/bin")));
+        processor.write_file("src/util/mod.rs", "").unwrap();
+        processor.write_file("src/lib.rs", "").unwrap();
+        assert_eq!(processor.common_prefix(), Some(PathBuf::from("src")));
+    }
+
+    #[test]
+    fn test_common_prefix_without_shared_directory() {
+        let dir = temp_dir("common_prefix_none");
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("srcs")).unwrap();
+        let mut processor = FileProcessor::new(&dir);
+        processor.write_file("src/lib.rs", "").unwrap();
+        processor.write_file("srcs/lib.rs", "").unwrap();
+        assert_eq!(processor.common_prefix(), None);
+
+        processor.write_file("README.md", "").unwrap();
+        assert_eq!(processor.common_prefix(), None);
+    }
+
+    #[test]
+    fn test_detect_encoding_of_fixtures() {
+        let fixture = Path::new(file!()).parent().unwrap().join("fixtures/encodings");
+        let mut processor = FileProcessor::new(&fixture);
+
+        let utf8 = processor.detect_encoding("utf8_bom.txt").unwrap();
+        assert_eq!(utf8, DetectedEncoding { encoding: Encoding::Utf8, has_bom: true });
+        let utf16 = processor.detect_encoding("utf16le_bom.txt").unwrap();
+        assert_eq!(utf16, DetectedEncoding { encoding: Encoding::Utf16Le, has_bom: true });
+        let ascii = processor.detect_encoding("ascii.txt").unwrap();
+        assert_eq!(ascii, DetectedEncoding { encoding: Encoding::Utf8, has_bom: false });
+        assert!(processor.stats().files.is_empty());
+
+        assert_eq!(processor.read_file_with_encoding("utf8_bom.txt", utf8.encoding).unwrap(), "café\n");
+        assert_eq!(processor.read_file_with_encoding("utf16le_bom.txt", utf16.encoding).unwrap(), "hi\n");
+    }
+
+    #[test]
+    fn test_normalize_latin1_crlf_file() {
+        let dir = temp_dir("normalize_file");
+        fs::write(dir.join("notes.txt"), b"caf\xe9\r\nna\xefve\r\n").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        assert!(processor.normalize_file("notes.txt", Encoding::Utf8, LineEnding::Lf).unwrap());
+        assert_eq!(fs::read_to_string(dir.join("notes.txt")).unwrap(), "café\nnaïve\n");
+        assert!(!processor.normalize_file("notes.txt", Encoding::Utf8, LineEnding::Lf).unwrap());
+
+        fs::write(dir.join("snow.txt"), "\u{2603}\n").unwrap();
+        assert!(matches!(
+            processor.normalize_file("snow.txt", Encoding::Latin1, LineEnding::Preserve),
+            Err(FileProcessorError::Encode { encoding: "ISO-8859-1", .. })
+        ));
+        assert_eq!(fs::read_to_string(dir.join("snow.txt")).unwrap(), "\u{2603}\n");
+    }
+
+    #[test]
+    fn test_planned_diffs_in_dry_run() {
+        let dir = temp_dir("planned_diffs");
+        fs::write(dir.join("lib.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+        let mut processor = FileProcessor::new(&dir).with_dry_run(true);
+
+        processor.write_file("lib.rs", "fn a() {}\nfn c() {}\n").unwrap();
+        processor.append_file("lib.rs", "fn d() {}\n").unwrap();
+        processor.write_file("new.rs", "fn main() {}\n").unwrap();
+        assert_eq!(fs::read_to_string(dir.join("lib.rs")).unwrap(), "fn a() {}\nfn b() {}\n");
+        assert!(!dir.join("new.rs").exists());
+
+        let line = |s: &str| s.to_string();
+        assert_eq!(
+            processor.planned_diffs(),
+            vec![
+                (
+                    line("lib.rs"),
+                    vec![
+                        DiffLine::Context(line("fn a() {}")),
+                        DiffLine::Removed(line("fn b() {}")),
+                        DiffLine::Added(line("fn c() {}")),
+                    ]
+                ),
+                (
+                    line("lib.rs"),
+                    vec![
+                        DiffLine::Context(line("fn a() {}")),
+                        DiffLine::Context(line("fn b() {}")),
+                        DiffLine::Added(line("fn d() {}")),
+                    ]
+                ),
+                (line("new.rs"), vec![DiffLine::Added(line("fn main() {}"))]),
+            ]
+        );
+        assert_eq!(processor.planned_writes().len(), 3);
     }
 }
@@ -0,0 +1,2 @@
+// FIXME: this file does not parse
+fn broken( {
@@ -0,0 +1,5 @@
+// Library entry point
+pub fn answer() -> u32 {
+    // TODO: compute this properly
+    42
+}
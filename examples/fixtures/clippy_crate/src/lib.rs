@@ -0,0 +1,4 @@
+/// Doubles a number with a deliberately needless `return`
+pub fn double(x: u32) -> u32 {
+    return x * 2;
+}
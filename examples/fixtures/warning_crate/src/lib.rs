@@ -0,0 +1,5 @@
+/// Returns the answer, leaving a deliberately unused variable behind
+pub fn answer() -> u32 {
+    let unused = 1;
+    42
+}
@@ -0,0 +1,107 @@
+// Minimal glob matching for relative paths
+//
+// Supports `*` and `?` within a path segment and `**` for any number of
+// segments. Patterns without a `/` are matched against the file name only.
+
+/// Checks whether a `/`-separated relative path matches a glob pattern
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let path_segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    if !pattern.contains('/') {
+        return path_segments
+            .last()
+            .map(|name| match_segment(pattern, name))
+            .unwrap_or(false);
+    }
+
+    let pattern_segments: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    match_segments(&pattern_segments, &path_segments)
+}
+
+/// Checks whether a path is excluded by a gitignore-style pattern
+///
+/// A trailing `/` matches directories only. Patterns with a `/` elsewhere are
+/// anchored to the start of the path; others match a name at any depth.
+pub fn ignore_match(pattern: &str, path: &str, is_dir: bool) -> bool {
+    let (pattern, dir_only) = match pattern.strip_suffix('/') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    if dir_only && !is_dir {
+        return false;
+    }
+    if pattern.contains('/') {
+        glob_match(pattern.trim_start_matches('/'), path)
+    } else {
+        path.rsplit('/')
+            .find(|s| !s.is_empty())
+            .is_some_and(|name| match_segment(pattern, name))
+    }
+}
+
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| match_segments(rest, &path[skip..])),
+        Some((segment, rest)) => {
+            !path.is_empty() && match_segment(segment, path[0]) && match_segments(rest, &path[1..])
+        }
+    }
+}
+
+fn match_segment(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            n = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_double_star_matches_any_depth() {
+        assert!(glob_match("**/*.rs", "main.rs"));
+        assert!(glob_match("**/*.rs", "src/bin/cli.rs"));
+        assert!(!glob_match("**/*.rs", "src/notes.txt"));
+        assert!(glob_match("src/**/mod.rs", "src/a/b/mod.rs"));
+        assert!(!glob_match("src/**/mod.rs", "tests/mod.rs"));
+    }
+
+    #[test]
+    fn test_segment_wildcards() {
+        assert!(glob_match("*.min.js", "dist/app.min.js"));
+        assert!(glob_match("file?.txt", "file1.txt"));
+        assert!(!glob_match("file?.txt", "file10.txt"));
+        assert!(!glob_match("src/*.rs", "src/a/b.rs"));
+    }
+
+    #[test]
+    fn test_ignore_patterns() {
+        assert!(ignore_match("target/", "target", true));
+        assert!(ignore_match("target/", "crates/cli/target", true));
+        assert!(!ignore_match("target/", "target", false));
+        assert!(ignore_match("*.min.js", "dist/app.min.js", false));
+        assert!(ignore_match("/build/out", "build/out", true));
+        assert!(!ignore_match("/build/out", "src/build/out", true));
+        assert!(!ignore_match("*.min.js", "src/app.js", false));
+    }
+}
@@ -0,0 +1,109 @@
+// Checks on `use` declarations in Rust source
+use std::collections::HashMap;
+
+use crate::syntax::{self, SyntaxError};
+
+/// An import that appears more than once in the same module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateImport {
+    /// The normalized import, such as `std::io::Read` or `a::b as c`
+    pub path: String,
+    /// 1-based lines of each occurrence, in source order
+    pub lines: Vec<u32>,
+}
+
+/// Reports imports declared more than once within a module
+///
+/// Grouped imports are flattened, so `use a::{b, c}` declares `a::b` and
+/// `a::c`, and `{self}` imports the parent path. An alias is part of the
+/// import, so `use a::b` and `use a::b as c` aren't duplicates; `as _`
+/// imports and `use` declarations inside function bodies are ignored.
+pub fn duplicate_imports(source: &str) -> Result<Vec<DuplicateImport>, SyntaxError> {
+    let file = syntax::parse_rust(source)?;
+    let mut duplicates = Vec::new();
+    collect_module(&file.items, &mut duplicates);
+    duplicates.sort_by_key(|d| d.lines[0]);
+    Ok(duplicates)
+}
+
+fn collect_module(items: &[syn::Item], duplicates: &mut Vec<DuplicateImport>) {
+    let mut seen: HashMap<String, Vec<u32>> = HashMap::new();
+    for item in items {
+        match item {
+            syn::Item::Use(item) => {
+                let prefix = if item.leading_colon.is_some() { "::" } else { "" };
+                flatten(&item.tree, prefix.to_string(), &mut seen);
+            }
+            syn::Item::Mod(syn::ItemMod { content: Some((_, nested)), .. }) => {
+                collect_module(nested, duplicates);
+            }
+            _ => {}
+        }
+    }
+    duplicates.extend(
+        seen.into_iter()
+            .filter(|(_, lines)| lines.len() > 1)
+            .map(|(path, lines)| DuplicateImport { path, lines }),
+    );
+}
+
+fn flatten(tree: &syn::UseTree, prefix: String, seen: &mut HashMap<String, Vec<u32>>) {
+    let join = |name: &dyn std::fmt::Display| {
+        if prefix.is_empty() || prefix == "::" {
+            format!("{}{}", prefix, name)
+        } else {
+            format!("{}::{}", prefix, name)
+        }
+    };
+    let (path, line) = match tree {
+        syn::UseTree::Path(path) => return flatten(&path.tree, join(&path.ident), seen),
+        syn::UseTree::Group(group) => {
+            for tree in &group.items {
+                flatten(tree, prefix.clone(), seen);
+            }
+            return;
+        }
+        // `{self}` imports the path it's nested in
+        syn::UseTree::Name(name) if name.ident == "self" => (prefix.clone(), name.ident.span()),
+        syn::UseTree::Name(name) => (join(&name.ident), name.ident.span()),
+        syn::UseTree::Rename(rename) if rename.rename == "_" => return,
+        syn::UseTree::Rename(rename) => {
+            let path = if rename.ident == "self" { prefix.clone() } else { join(&rename.ident) };
+            (format!("{} as {}", path, rename.rename), rename.rename.span())
+        }
+        syn::UseTree::Glob(glob) => (join(&"*"), glob.star_token.span),
+    };
+    seen.entry(path).or_default().push(line.start().line as u32);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_duplicate() {
+        let source = "use std::io;\nuse std::fs::File;\nuse std::io::{self, Read};\nuse std::fs::{File, read};\n";
+        assert_eq!(
+            duplicate_imports(source).unwrap(),
+            vec![
+                DuplicateImport { path: "std::io".to_string(), lines: vec![1, 3] },
+                DuplicateImport { path: "std::fs::File".to_string(), lines: vec![2, 4] },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_alias_is_not_duplicate() {
+        let source = "use a::b;\nuse a::b as c;\nuse a::*;\nuse a::Trait as _;\nuse a::Trait as _;\n";
+        assert!(duplicate_imports(source).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_duplicates_are_per_module() {
+        let source = "use a::b;\nmod inner {\n    use a::b;\n    use a::*;\n    use a::*;\n}\n";
+        assert_eq!(
+            duplicate_imports(source).unwrap(),
+            vec![DuplicateImport { path: "a::*".to_string(), lines: vec![4, 5] }]
+        );
+    }
+}
@@ -0,0 +1,109 @@
+// Programming language detection for processed files
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// Languages recognized by the feedback tooling
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Go,
+    Markdown,
+    Unknown,
+}
+
+impl Language {
+    /// Human readable name of the language
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Language::Rust => "Rust",
+            Language::Python => "Python",
+            Language::JavaScript => "JavaScript",
+            Language::TypeScript => "TypeScript",
+            Language::Go => "Go",
+            Language::Markdown => "Markdown",
+            Language::Unknown => "Unknown",
+        }
+    }
+
+    /// Resolves a language from a file extension without the leading dot
+    pub fn from_extension(extension: &str) -> Language {
+        match extension.to_lowercase().as_str() {
+            "rs" => Language::Rust,
+            "py" | "pyw" | "pyi" => Language::Python,
+            "js" | "mjs" | "cjs" | "jsx" => Language::JavaScript,
+            "ts" | "mts" | "cts" | "tsx" => Language::TypeScript,
+            "go" => Language::Go,
+            "md" | "markdown" => Language::Markdown,
+            _ => Language::Unknown,
+        }
+    }
+
+    /// Resolves a language from a `#!` interpreter line
+    pub fn from_shebang(line: &str) -> Option<Language> {
+        let command = line.strip_prefix("#!")?.trim();
+        let mut parts = command.split_whitespace();
+        let mut interpreter = parts.next()?.rsplit('/').next()?;
+        if interpreter == "env" {
+            interpreter = parts.find(|part| !part.starts_with('-'))?;
+        }
+        let name = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+        Some(match name {
+            "python" => Language::Python,
+            "node" | "nodejs" => Language::JavaScript,
+            "deno" | "ts-node" | "tsx" => Language::TypeScript,
+            "rust-script" => Language::Rust,
+            _ => Language::Unknown,
+        })
+    }
+}
+
+/// Detects the language of a file by extension, falling back to its shebang line
+///
+/// Returns `None` when the file has no extension and no shebang line could be
+/// read, and `Some(Language::Unknown)` when it was inspected but not recognized.
+pub fn detect_language<P: AsRef<Path>>(path: P) -> Option<Language> {
+    let path = path.as_ref();
+    if let Some(extension) = path.extension() {
+        return Some(Language::from_extension(&extension.to_string_lossy()));
+    }
+
+    let file = File::open(path).ok()?;
+    let mut first_line = String::new();
+    BufReader::new(file).read_line(&mut first_line).ok()?;
+    Language::from_shebang(&first_line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language_by_extension() {
+        assert_eq!(detect_language("src/main.rs"), Some(Language::Rust));
+        assert_eq!(detect_language("script.py"), Some(Language::Python));
+        assert_eq!(detect_language("README.md").map(|l| l.display_name()), Some("Markdown"));
+    }
+
+    #[test]
+    fn test_detect_language_from_shebang() {
+        let dir = std::env::temp_dir().join(format!("language_shebang_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("run"), "#!/usr/bin/env python3\nprint('hi')\n").unwrap();
+        std::fs::write(dir.join("serve"), "#!/usr/local/bin/node\n").unwrap();
+        std::fs::write(dir.join("plain"), "no shebang here\n").unwrap();
+
+        assert_eq!(detect_language(dir.join("run")), Some(Language::Python));
+        assert_eq!(detect_language(dir.join("serve")), Some(Language::JavaScript));
+        assert_eq!(detect_language(dir.join("plain")), None);
+    }
+
+    #[test]
+    fn test_detect_language_unknown_extension() {
+        assert_eq!(detect_language("data.xyz"), Some(Language::Unknown));
+        assert_eq!(Language::Unknown.display_name(), "Unknown");
+    }
+}
@@ -0,0 +1,189 @@
+// Lightweight splitting of Rust-style source into code, comments, and literals
+//
+// This isn't a full tokenizer; it only knows enough to tell whether a byte
+// offset is inside a comment or a string/char literal.
+
+/// What a run of source text is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentKind {
+    Code,
+    LineComment,
+    BlockComment,
+    Str,
+    Char,
+}
+
+impl SegmentKind {
+    /// Checks whether the segment is a line or block comment
+    pub fn is_comment(&self) -> bool {
+        matches!(self, SegmentKind::LineComment | SegmentKind::BlockComment)
+    }
+}
+
+/// A contiguous byte range of source text of a single kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Segment {
+    pub kind: SegmentKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Splits source into code, comment, and literal segments covering every byte
+///
+/// Handles nested block comments, escaped quotes, raw strings such as
+/// `r#"..."#`, and tells char literals apart from lifetimes.
+pub fn segments(source: &str) -> Vec<Segment> {
+    let bytes = source.as_bytes();
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let (kind, end) = if bytes[i..].starts_with(b"//") {
+            let end = source[i..].find('\n').map(|n| i + n).unwrap_or(bytes.len());
+            (SegmentKind::LineComment, end)
+        } else if bytes[i..].starts_with(b"/*") {
+            (SegmentKind::BlockComment, block_comment_end(bytes, i))
+        } else if let Some(end) = raw_string_end(bytes, i) {
+            (SegmentKind::Str, end)
+        } else if bytes[i] == b'"' {
+            (SegmentKind::Str, quoted_end(bytes, i, b'"'))
+        } else if let Some(end) = char_literal_end(source, i) {
+            (SegmentKind::Char, end)
+        } else {
+            let len = source[i..].chars().next().map(char::len_utf8).unwrap_or(1);
+            (SegmentKind::Code, i + len)
+        };
+
+        match segments.last_mut() {
+            Some(last) if kind == SegmentKind::Code && last.kind == SegmentKind::Code => last.end = end,
+            _ => segments.push(Segment { kind, start: i, end }),
+        }
+        i = end;
+    }
+
+    segments
+}
+
+fn block_comment_end(bytes: &[u8], start: usize) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(b"/*") {
+            depth += 1;
+            i += 2;
+        } else if bytes[i..].starts_with(b"*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+fn quoted_end(bytes: &[u8], start: usize, quote: u8) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b if b == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+fn is_ident_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte >= 0x80
+}
+
+/// Matches `r"..."`, `r#"..."#`, and `br#"..."#` starting at `start`
+fn raw_string_end(bytes: &[u8], start: usize) -> Option<usize> {
+    if start > 0 && is_ident_byte(bytes[start - 1]) {
+        return None;
+    }
+    let mut i = start;
+    if bytes.get(i) == Some(&b'b') {
+        i += 1;
+    }
+    if bytes.get(i) != Some(&b'r') {
+        return None;
+    }
+    i += 1;
+    let hashes = bytes[i..].iter().take_while(|&&b| b == b'#').count();
+    i += hashes;
+    if bytes.get(i) != Some(&b'"') {
+        return None;
+    }
+    i += 1;
+
+    while i < bytes.len() {
+        if bytes[i] == b'"' && bytes[i + 1..].iter().take(hashes).filter(|&&b| b == b'#').count() == hashes {
+            return Some(i + 1 + hashes);
+        }
+        i += 1;
+    }
+    Some(bytes.len())
+}
+
+/// Matches a char literal like `'a'` or `'\n'`, but not a lifetime like `'a`
+fn char_literal_end(source: &str, start: usize) -> Option<usize> {
+    let bytes = source.as_bytes();
+    if bytes[start] != b'\'' {
+        return None;
+    }
+    if bytes.get(start + 1) == Some(&b'\\') {
+        return Some(quoted_end(bytes, start, b'\''));
+    }
+    let c = source[start + 1..].chars().next()?;
+    let close = start + 1 + c.len_utf8();
+    (bytes.get(close) == Some(&b'\'')).then_some(close + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<(SegmentKind, &str)> {
+        segments(source)
+            .into_iter()
+            .map(|s| (s.kind, &source[s.start..s.end]))
+            .collect()
+    }
+
+    #[test]
+    fn test_comments_and_strings() {
+        let source = "let s = \"// not \\\" a comment\"; // real\n/* a /* nested */ b */x";
+        assert_eq!(
+            kinds(source),
+            vec![
+                (SegmentKind::Code, "let s = "),
+                (SegmentKind::Str, "\"// not \\\" a comment\""),
+                (SegmentKind::Code, "; "),
+                (SegmentKind::LineComment, "// real"),
+                (SegmentKind::Code, "\n"),
+                (SegmentKind::BlockComment, "/* a /* nested */ b */"),
+                (SegmentKind::Code, "x"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_raw_strings_chars_and_lifetimes() {
+        let source = "fn f<'a>(x: &'a str) -> char { let r = r#\"say \"hi\" // no\"#; '\"' }";
+        let literals: Vec<_> = kinds(source)
+            .into_iter()
+            .filter(|(kind, _)| *kind != SegmentKind::Code)
+            .collect();
+        assert_eq!(
+            literals,
+            vec![
+                (SegmentKind::Str, "r#\"say \"hi\" // no\"#"),
+                (SegmentKind::Char, "'\"'"),
+            ]
+        );
+    }
+}
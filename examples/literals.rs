@@ -0,0 +1,95 @@
+// Extraction of string literals from Rust source
+use proc_macro2::{TokenStream, TokenTree};
+
+use crate::syntax::{self, SyntaxError};
+
+/// A string literal found in source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringLiteral {
+    /// The literal's value with escape sequences resolved
+    pub value: String,
+    /// Line the literal starts on
+    pub line: u32,
+    /// 1-based column of the literal's opening quote or `r` prefix
+    pub column: u32,
+    /// Whether it's written as a raw string such as `r#"..."#`
+    pub raw: bool,
+}
+
+/// Lists every string literal in Rust source, in source order
+///
+/// Literals inside macro calls such as `println!` are included, while byte
+/// strings, char literals, and doc comments are not.
+pub fn string_literals(source: &str) -> Result<Vec<StringLiteral>, SyntaxError> {
+    syntax::parse_rust(source)?;
+    let tokens: TokenStream = source.parse().expect("source already parsed");
+    let mut literals = Vec::new();
+    collect_literals(tokens, &mut literals);
+    Ok(literals)
+}
+
+fn collect_literals(tokens: TokenStream, out: &mut Vec<StringLiteral>) {
+    let mut after_pound = false;
+    for token in tokens {
+        // Inner attributes put a `!` between the `#` and the brackets
+        let is_pound = match &token {
+            TokenTree::Punct(punct) => punct.as_char() == '#' || (after_pound && punct.as_char() == '!'),
+            _ => false,
+        };
+        match token {
+            // `///` and `//!` comments reach the token stream as `#[doc = "..."]`
+            TokenTree::Group(group) if after_pound && is_doc_attribute(&group.stream()) => {}
+            TokenTree::Group(group) => collect_literals(group.stream(), out),
+            TokenTree::Literal(literal) => {
+                let raw = literal.to_string().starts_with('r');
+                let start = literal.span().start();
+                let (line, column) = (start.line as u32, start.column as u32 + 1);
+                if let syn::Lit::Str(lit) = syn::Lit::new(literal) {
+                    out.push(StringLiteral { value: lit.value(), line, column, raw });
+                }
+            }
+            _ => {}
+        }
+        after_pound = is_pound;
+    }
+}
+
+fn is_doc_attribute(tokens: &TokenStream) -> bool {
+    let mut tokens = tokens.clone().into_iter();
+    matches!(tokens.next(), Some(TokenTree::Ident(ident)) if ident == "doc")
+        && matches!(tokens.next(), Some(TokenTree::Punct(punct)) if punct.as_char() == '=')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normal_and_raw_literals() {
+        let source = r##"//! Neither are module docs
+/// Docs are not literals
+fn main() {
+    let greeting = "hello\tworld\u{21}";
+    let pattern = r#"C:\path "quoted""#;
+    let c = 'a';
+    let bytes = b"bytes";
+    println!("{} {}", greeting, "multi
+line");
+}
+"##;
+        assert_eq!(
+            string_literals(source).unwrap(),
+            vec![
+                StringLiteral { value: "hello\tworld!".to_string(), line: 4, column: 20, raw: false },
+                StringLiteral { value: "C:\\path \"quoted\"".to_string(), line: 5, column: 19, raw: true },
+                StringLiteral { value: "{} {}".to_string(), line: 8, column: 14, raw: false },
+                StringLiteral { value: "multi\nline".to_string(), line: 8, column: 33, raw: false },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_source() {
+        assert!(string_literals("fn main() { let s = \"open; }").is_err());
+    }
+}
@@ -0,0 +1,161 @@
+// Scanning comments for TODO-style markers
+use std::path::Path;
+
+use crate::analyzer::Analyzer;
+use crate::diagnostics::{Diagnostic, Level};
+use crate::language::{detect_language, Language};
+use crate::lexer;
+use crate::position::Position;
+
+/// Markers reported by default
+pub const DEFAULT_MARKERS: [&str; 4] = ["TODO", "FIXME", "HACK", "XXX"];
+
+/// A marker found inside a comment
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MarkerHit {
+    pub kind: String,
+    pub position: Position,
+    pub text: String,
+}
+
+/// Finds configurable markers inside comments, ignoring string literals
+#[derive(Debug, Clone)]
+pub struct MarkerScanner {
+    markers: Vec<String>,
+}
+
+impl Default for MarkerScanner {
+    fn default() -> Self {
+        MarkerScanner {
+            markers: DEFAULT_MARKERS.iter().map(|m| m.to_string()).collect(),
+        }
+    }
+}
+
+impl MarkerScanner {
+    /// Creates a scanner for the default markers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the set of markers to look for
+    pub fn with_markers(mut self, markers: Vec<String>) -> Self {
+        self.markers = markers;
+        self
+    }
+
+    /// Scans source for markers, reporting each comment line at most once
+    ///
+    /// Markers must appear as whole words, so `TODOS` doesn't match `TODO`.
+    /// The hit is positioned at the marker, and its text is the comment line
+    /// from the marker onwards.
+    pub fn scan(&self, source: &str) -> Vec<MarkerHit> {
+        let mut hits = Vec::new();
+        for segment in lexer::segments(source) {
+            if !segment.kind.is_comment() {
+                continue;
+            }
+            let mut line_start = segment.start;
+            for line in source[segment.start..segment.end].split_inclusive('\n') {
+                let offset = line_start;
+                line_start += line.len();
+                if let Some((kind, index)) = self.find_marker(line) {
+                    hits.push(MarkerHit {
+                        kind: kind.to_string(),
+                        position: Position::from_offset(source, offset + index),
+                        text: line[index..].trim_end().trim_end_matches("*/").trim_end().to_string(),
+                    });
+                }
+            }
+        }
+        hits
+    }
+
+    /// Finds the earliest whole-word marker in a line
+    fn find_marker<'a>(&'a self, line: &str) -> Option<(&'a str, usize)> {
+        let is_word = |c: char| c.is_alphanumeric() || c == '_';
+        self.markers
+            .iter()
+            .flat_map(|marker| {
+                line.match_indices(marker.as_str())
+                    .filter(|(index, _)| {
+                        let before = line[..*index].chars().next_back();
+                        let after = line[index + marker.len()..].chars().next();
+                        !before.is_some_and(is_word) && !after.is_some_and(is_word)
+                    })
+                    .map(move |(index, _)| (marker.as_str(), index))
+            })
+            .min_by_key(|(_, index)| *index)
+    }
+}
+
+/// Reports each marker in a Rust file as a note
+impl Analyzer for MarkerScanner {
+    fn name(&self) -> &str {
+        "markers"
+    }
+
+    fn analyze(&self, path: &Path, source: &str) -> Vec<Diagnostic> {
+        if detect_language(path) != Some(Language::Rust) {
+            return Vec::new();
+        }
+        self.scan(source)
+            .into_iter()
+            .map(|hit| Diagnostic {
+                level: Level::Note,
+                message: hit.text,
+                file: crate::slash_path(path),
+                position: hit.position,
+                span_text: hit.kind,
+                code: None,
+                analyzer: None,
+            })
+            .collect()
+    }
+}
+
+/// Finds the default markers inside comments
+pub fn find_markers(source: &str) -> Vec<MarkerHit> {
+    MarkerScanner::default().scan(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marker_in_comment_is_found() {
+        let source = "fn main() {\n    // TODO: handle errors\n    run();\n    /* FIXME later */\n}\n";
+        assert_eq!(
+            find_markers(source),
+            vec![
+                MarkerHit {
+                    kind: "TODO".to_string(),
+                    position: Position::new(2, 8),
+                    text: "TODO: handle errors".to_string(),
+                },
+                MarkerHit {
+                    kind: "FIXME".to_string(),
+                    position: Position::new(4, 8),
+                    text: "FIXME later".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_marker_in_string_is_ignored() {
+        let source = "let s = \"TODO: not a real marker\";\nlet t = r#\"FIXME\"#;\n// TODOS are not markers\n";
+        assert!(find_markers(source).is_empty());
+    }
+
+    #[test]
+    fn test_custom_markers() {
+        let scanner = MarkerScanner::new().with_markers(vec!["NOTE".to_string()]);
+        let hits = scanner.scan("// NOTE: custom\n// TODO: default only\n");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].kind, "NOTE");
+        assert_eq!(hits[0].position, Position::new(1, 4));
+    }
+}
@@ -0,0 +1,288 @@
+// Source code and prose metrics such as line counts
+use crate::language::Language;
+use crate::lexer;
+
+/// Breakdown of the lines in a source file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LineCount {
+    pub total: usize,
+    pub code: usize,
+    pub comment: usize,
+    pub blank: usize,
+}
+
+/// Line and block comment markers used to classify lines
+///
+/// Built-in languages convert into their syntax with `From<Language>`; other
+/// languages can be described directly, such as Lua's `--` and `--[[ ]]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommentSyntax {
+    pub line: Vec<String>,
+    pub block: Vec<(String, String)>,
+}
+
+impl CommentSyntax {
+    /// Creates a syntax from line comment prefixes and block comment delimiters
+    pub fn new(line: &[&str], block: &[(&str, &str)]) -> Self {
+        CommentSyntax {
+            line: line.iter().map(|marker| marker.to_string()).collect(),
+            block: block
+                .iter()
+                .map(|(start, end)| (start.to_string(), end.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl From<Language> for CommentSyntax {
+    fn from(language: Language) -> Self {
+        match language {
+            Language::Rust | Language::JavaScript | Language::TypeScript | Language::Go => {
+                CommentSyntax::new(&["//"], &[("/*", "*/")])
+            }
+            Language::Python => CommentSyntax::new(&["#"], &[]),
+            Language::Markdown => CommentSyntax::new(&[], &[("<!--", "-->")]),
+            Language::Unknown => CommentSyntax::default(),
+        }
+    }
+}
+
+/// Counts total, code, comment, and blank lines in source text
+///
+/// Accepts a `Language` or an explicit `CommentSyntax`. A line with any code
+/// outside comments counts as code, even if it also has a trailing comment.
+///
+/// The `//` and `/* */` syntax of Rust, JavaScript, TypeScript, and Go is
+/// classified with `lexer::segments`, so raw strings, char literals, and
+/// nested block comments are handled. For other syntaxes, block comments are
+/// tracked across lines and take precedence over line comments sharing a
+/// prefix, and comment markers inside double-quoted strings are ignored.
+pub fn count_lines<S: Into<CommentSyntax>>(source: &str, syntax: S) -> LineCount {
+    let syntax = syntax.into();
+    if syntax == CommentSyntax::from(Language::Rust) {
+        return count_lexed_lines(source);
+    }
+    let CommentSyntax { line: line_markers, block: block_markers } = syntax;
+    let mut count = LineCount::default();
+    let mut block_end: Option<&str> = None;
+    let mut in_string = false;
+
+    for line in source.lines() {
+        count.total += 1;
+        if line.trim().is_empty() {
+            count.blank += 1;
+            continue;
+        }
+
+        let (mut has_code, mut has_comment) = (false, false);
+        let mut rest = line;
+        while let Some(c) = rest.chars().next() {
+            if let Some(end) = block_end {
+                has_comment = true;
+                match rest.find(end) {
+                    Some(index) => {
+                        rest = &rest[index + end.len()..];
+                        block_end = None;
+                    }
+                    None => rest = "",
+                }
+                continue;
+            }
+            if in_string {
+                has_code = true;
+                if c == '\\' {
+                    rest = skip_chars(rest, 2);
+                    continue;
+                }
+                in_string = c != '"';
+            } else if c.is_whitespace() {
+                // Whitespace is neither code nor comment
+            } else if let Some((start, end)) = block_markers.iter().find(|(start, _)| rest.starts_with(start.as_str())) {
+                has_comment = true;
+                block_end = Some(end);
+                rest = &rest[start.len()..];
+                continue;
+            } else if line_markers.iter().any(|marker| rest.starts_with(marker.as_str())) {
+                has_comment = true;
+                break;
+            } else {
+                has_code = true;
+                in_string = c == '"';
+            }
+            rest = skip_chars(rest, 1);
+        }
+
+        if has_code {
+            count.code += 1;
+        } else if has_comment {
+            count.comment += 1;
+        }
+    }
+
+    count
+}
+
+/// Counts lines of C-style source by the lexer segments they contain
+fn count_lexed_lines(source: &str) -> LineCount {
+    let total = source.lines().count();
+    let (mut has_code, mut has_comment) = (vec![false; total], vec![false; total]);
+    let mut line = 0;
+    for segment in lexer::segments(source) {
+        for (index, piece) in source[segment.start..segment.end].split('\n').enumerate() {
+            line += usize::from(index > 0);
+            if line >= total || piece.trim().is_empty() {
+                continue;
+            }
+            if segment.kind.is_comment() {
+                has_comment[line] = true;
+            } else {
+                has_code[line] = true;
+            }
+        }
+    }
+
+    let mut count = LineCount { total, ..LineCount::default() };
+    for (line, text) in source.lines().enumerate() {
+        if text.trim().is_empty() {
+            count.blank += 1;
+        } else if has_code[line] {
+            count.code += 1;
+        } else if has_comment[line] {
+            count.comment += 1;
+        }
+    }
+    count
+}
+
+/// Word, character, and sentence counts for prose such as a README
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextSummary {
+    pub word_count: usize,
+    pub char_count: usize,
+    pub sentence_count: usize,
+    /// Length in characters of the longest line
+    pub longest_line: usize,
+}
+
+/// Summarizes prose text
+///
+/// Words are whitespace-separated. A sentence ends at a run of `.`, `!`, or
+/// `?` followed by whitespace or the end of the text, and trailing text
+/// without a terminator counts as one more sentence.
+pub fn text_summary(source: &str) -> TextSummary {
+    let mut sentence_count = 0;
+    let mut in_sentence = false;
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        let terminator = matches!(c, '.' | '!' | '?');
+        let next_is_break = chars.peek().is_none_or(|next| next.is_whitespace());
+        if terminator && in_sentence && next_is_break {
+            sentence_count += 1;
+            in_sentence = false;
+        } else if !c.is_whitespace() && !terminator {
+            in_sentence = true;
+        }
+    }
+    if in_sentence {
+        sentence_count += 1;
+    }
+
+    TextSummary {
+        word_count: source.split_whitespace().count(),
+        char_count: source.chars().count(),
+        sentence_count,
+        longest_line: source.lines().map(|line| line.chars().count()).max().unwrap_or(0),
+    }
+}
+
+fn skip_chars(text: &str, count: usize) -> &str {
+    let index = text.char_indices().nth(count).map(|(i, _)| i).unwrap_or(text.len());
+    &text[index..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_rust_lines() {
+        let source = r#"// Line comment
+/* Block comment
+   spanning lines
+*/
+
+fn main() { // trailing comment
+    let url = "http://example.com"; /* inline */
+    /* before */ let x = 1;
+
+    println!("{}", x);
+}
+"#;
+        assert_eq!(
+            count_lines(source, Language::Rust),
+            LineCount { total: 11, code: 5, comment: 4, blank: 2 }
+        );
+    }
+
+    #[test]
+    fn test_count_python_lines() {
+        let source = "# comment\nx = '#not'  # trailing\n\n# another\nprint(x)\n";
+        assert_eq!(
+            count_lines(source, Language::Python),
+            LineCount { total: 5, code: 2, comment: 2, blank: 1 }
+        );
+    }
+
+    #[test]
+    fn test_count_with_custom_syntax() {
+        let lua = CommentSyntax::new(&["--"], &[("--[[", "]]")]);
+        let source = "-- line comment\n--[[ block\n   comment ]]\nlocal x = 1 -- trailing\n\nprint(x)\n";
+        assert_eq!(
+            count_lines(source, lua),
+            LineCount { total: 6, code: 2, comment: 3, blank: 1 }
+        );
+
+        // None of the built-in languages treat `--` as a comment
+        assert_eq!(count_lines(source, Language::Rust).comment, 0);
+    }
+
+    #[test]
+    fn test_text_summary_of_paragraph() {
+        let source = "Fast tools help. Do they really?\nYes! Version 1.2 is out...\nsee docs";
+        assert_eq!(
+            text_summary(source),
+            TextSummary { word_count: 13, char_count: 68, sentence_count: 5, longest_line: 32 }
+        );
+    }
+
+    #[test]
+    fn test_text_summary_of_empty_string() {
+        assert_eq!(text_summary(""), TextSummary::default());
+        assert_eq!(text_summary("  \n\t").sentence_count, 0);
+    }
+
+    #[test]
+    fn test_quote_char_literal_does_not_start_string() {
+        let source = "let q = '\"';\n// a comment\nlet e = '\\'';\nlet b = '\\\\';\n// another\nfn f<'a>(s: &'a str) {}\nlet u = '\\u{1F600}'; // smile\n";
+        let count = count_lines(source, Language::Rust);
+        assert_eq!(count, LineCount { total: 7, code: 5, comment: 2, blank: 0 });
+    }
+
+    #[test]
+    fn test_raw_string_quote_does_not_start_string() {
+        let source = "let s = r#\"a\"b\"#;\n// comment\nlet t = r\"multi\n// still a string\";\n";
+        assert_eq!(
+            count_lines(source, Language::Rust),
+            LineCount { total: 4, code: 3, comment: 1, blank: 0 }
+        );
+    }
+
+    #[test]
+    fn test_nested_block_comments() {
+        let source = "/* outer /* inner */ still */\n/* a\n/* b */\nc */\nfn main() {}\n";
+        assert_eq!(
+            count_lines(source, Language::Rust),
+            LineCount { total: 5, code: 1, comment: 4, blank: 0 }
+        );
+    }
+}
@@ -0,0 +1,161 @@
+// Finding calls that can panic at runtime
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+
+use crate::position::Position;
+use crate::syntax::{self, SyntaxError};
+
+/// How a call can panic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PanicKind {
+    Unwrap,
+    Expect,
+    Panic,
+    Todo,
+    Unimplemented,
+}
+
+/// A potentially panicking call found in source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanicCall {
+    pub kind: PanicKind,
+    pub line: u32,
+    /// Source text of the value `.unwrap()` or `.expect()` is called on
+    pub receiver: Option<String>,
+}
+
+/// Finds `.unwrap()` and `.expect(..)` calls and `panic!`, `todo!`, and `unimplemented!` macros
+///
+/// With `skip_tests`, `#[test]` functions and `#[cfg(test)]` items are left
+/// out. Macros nested inside other macros' arguments aren't inspected.
+pub fn panic_calls(source: &str, skip_tests: bool) -> Result<Vec<PanicCall>, SyntaxError> {
+    let file = syntax::parse_rust(source)?;
+    let mut visitor = PanicVisitor { source, skip_tests, calls: Vec::new() };
+    visitor.visit_file(&file);
+    visitor.calls.sort_by_key(|call| call.line);
+    Ok(visitor.calls)
+}
+
+struct PanicVisitor<'a> {
+    source: &'a str,
+    skip_tests: bool,
+    calls: Vec<PanicCall>,
+}
+
+impl PanicVisitor<'_> {
+    fn source_text(&self, span: proc_macro2::Span) -> Option<String> {
+        let offset = |at: proc_macro2::LineColumn| {
+            Position::new(at.line as u32, at.column as u32 + 1).to_offset(self.source)
+        };
+        let (start, end) = (offset(span.start())?, offset(span.end())?);
+        self.source.get(start..end).map(str::to_string)
+    }
+
+    fn is_test_code(&self, attrs: &[syn::Attribute]) -> bool {
+        self.skip_tests
+            && attrs.iter().any(|attr| {
+                attr.path().is_ident("test")
+                    || (attr.path().is_ident("cfg")
+                        && attr.meta.require_list().is_ok_and(|list| list.tokens.to_string() == "test"))
+            })
+    }
+}
+
+impl<'ast> Visit<'ast> for PanicVisitor<'_> {
+    fn visit_item_mod(&mut self, item: &'ast syn::ItemMod) {
+        if !self.is_test_code(&item.attrs) {
+            visit::visit_item_mod(self, item);
+        }
+    }
+
+    fn visit_item_fn(&mut self, item: &'ast syn::ItemFn) {
+        if !self.is_test_code(&item.attrs) {
+            visit::visit_item_fn(self, item);
+        }
+    }
+
+    fn visit_impl_item_fn(&mut self, item: &'ast syn::ImplItemFn) {
+        if !self.is_test_code(&item.attrs) {
+            visit::visit_impl_item_fn(self, item);
+        }
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        let kind = match (call.method.to_string().as_str(), call.args.len()) {
+            ("unwrap", 0) => Some(PanicKind::Unwrap),
+            ("expect", 1) => Some(PanicKind::Expect),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            self.calls.push(PanicCall {
+                kind,
+                line: call.method.span().start().line as u32,
+                receiver: self.source_text(call.receiver.span()),
+            });
+        }
+        visit::visit_expr_method_call(self, call);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        let kind = match mac.path.segments.last().map(|s| s.ident.to_string()).as_deref() {
+            Some("panic") => Some(PanicKind::Panic),
+            Some("todo") => Some(PanicKind::Todo),
+            Some("unimplemented") => Some(PanicKind::Unimplemented),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            self.calls.push(PanicCall {
+                kind,
+                line: mac.path.span().start().line as u32,
+                receiver: None,
+            });
+        }
+        visit::visit_macro(self, mac);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"fn load(path: &str) -> String {
+    let text = std::fs::read_to_string(path).unwrap();
+    let n: u32 = text.trim().parse().expect("a number");
+    if n == 0 {
+        panic!("empty");
+    }
+    todo!()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn loads() {
+        super::load("x").len().checked_sub(1).unwrap();
+    }
+}
+"#;
+
+    #[test]
+    fn test_finds_panicking_calls() {
+        let calls = panic_calls(SOURCE, false).unwrap();
+        let found: Vec<_> = calls.iter().map(|c| (c.kind, c.line, c.receiver.as_deref())).collect();
+        assert_eq!(
+            found,
+            vec![
+                (PanicKind::Unwrap, 2, Some("std::fs::read_to_string(path)")),
+                (PanicKind::Expect, 3, Some("text.trim().parse()")),
+                (PanicKind::Panic, 5, None),
+                (PanicKind::Todo, 7, None),
+                (PanicKind::Unwrap, 14, Some("super::load(\"x\").len().checked_sub(1)")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skip_tests() {
+        let calls = panic_calls(SOURCE, true).unwrap();
+        assert_eq!(calls.len(), 4);
+        assert!(calls.iter().all(|call| call.line < 10));
+    }
+}
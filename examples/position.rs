@@ -0,0 +1,154 @@
+// 1-based source positions and snippets shared by markers, syntax errors, and diagnostics
+use std::fmt;
+
+/// A line and column in source text, both 1-based
+///
+/// Columns count characters rather than bytes, matching rustc. Positions
+/// order by line, then column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    pub line: u32,
+    pub column: u32,
+}
+
+impl Position {
+    /// Creates a position from a 1-based line and column
+    pub fn new(line: u32, column: u32) -> Self {
+        Position { line, column }
+    }
+
+    /// Converts a byte offset within source into a position
+    ///
+    /// Offsets past the end map to the position just after the last
+    /// character, and offsets inside a multi-byte character map to that character.
+    pub fn from_offset(source: &str, offset: usize) -> Self {
+        let mut offset = offset.min(source.len());
+        while !source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        Position {
+            line: before.matches('\n').count() as u32 + 1,
+            column: before[line_start..].chars().count() as u32 + 1,
+        }
+    }
+
+    /// Converts the position back into a byte offset within source
+    ///
+    /// Returns `None` if the line or column doesn't exist in the source.
+    pub fn to_offset(&self, source: &str) -> Option<usize> {
+        let line_start = if self.line == 1 {
+            0
+        } else {
+            source.match_indices('\n').nth(self.line.checked_sub(2)? as usize)?.0 + 1
+        };
+        let line = &source[line_start..];
+        let line = &line[..line.find('\n').unwrap_or(line.len())];
+        let column = self.column.checked_sub(1)? as usize;
+        match line.char_indices().nth(column) {
+            Some((index, _)) => Some(line_start + index),
+            None if column == line.chars().count() => Some(line_start + line.len()),
+            None => None,
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Renders the lines around a position with line numbers and a `^` under its column
+///
+/// Shows up to `context` lines before and after the position's line. Lines
+/// past the end of the source are clamped to the last line and columns past
+/// the end of a line put the caret just after it. Tabs before the column are
+/// repeated in the caret line so it stays aligned. Empty source renders as
+/// an empty string.
+pub fn render_snippet(source: &str, pos: Position, context: usize) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    if lines.is_empty() {
+        return String::new();
+    }
+    let target = (pos.line.max(1) as usize).min(lines.len());
+    let first = target.saturating_sub(context).max(1);
+    let last = (target + context).min(lines.len());
+    let width = last.to_string().len();
+
+    let mut snippet = String::new();
+    for number in first..=last {
+        let line = lines[number - 1];
+        snippet.push_str(&format!("{:>width$} | {}\n", number, line));
+        if number == target {
+            let column = (pos.column.max(1) as usize - 1).min(line.chars().count());
+            let padding: String = line
+                .chars()
+                .take(column)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            snippet.push_str(&format!("{:>width$} | {}^\n", "", padding));
+        }
+    }
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_offset_in_multi_line_source() {
+        let source = "fn main() {\n    let café = 1;\n    run();\n}\n";
+        let offset = source.find("run").unwrap();
+        assert_eq!(Position::from_offset(source, offset), Position::new(3, 5));
+        assert_eq!(Position::from_offset(source, 0), Position::new(1, 1));
+
+        // Columns count characters, so the two-byte `é` is one column
+        let offset = source.find(" = 1").unwrap();
+        assert_eq!(Position::from_offset(source, offset), Position::new(2, 13));
+        assert_eq!(Position::from_offset(source, source.len() + 10), Position::new(5, 1));
+    }
+
+    #[test]
+    fn test_to_offset_round_trip() {
+        let source = "let a = 'é';\nlet b = 2;\n";
+        for offset in source.char_indices().map(|(i, _)| i) {
+            assert_eq!(Position::from_offset(source, offset).to_offset(source), Some(offset));
+        }
+        assert_eq!(Position::new(2, 20).to_offset(source), None);
+        assert_eq!(Position::new(0, 1).to_offset(source), None);
+    }
+
+    #[test]
+    fn test_positions_sort_by_line_then_column() {
+        let mut positions = vec![Position::new(2, 1), Position::new(1, 9), Position::new(1, 3)];
+        positions.sort();
+        assert_eq!(positions, vec![Position::new(1, 3), Position::new(1, 9), Position::new(2, 1)]);
+        assert_eq!(Position::new(4, 7).to_string(), "4:7");
+    }
+
+    #[test]
+    fn test_render_snippet_mid_file() {
+        let source = "fn main() {\n    let x = 1;\n    let y: u32 = \"a\";\n    run(x, y);\n}\n";
+        let snippet = render_snippet(source, Position::new(3, 18), 1);
+        assert_eq!(
+            snippet,
+            "2 |     let x = 1;\n3 |     let y: u32 = \"a\";\n  |                  ^\n4 |     run(x, y);\n"
+        );
+        let caret_line = snippet.lines().nth(2).unwrap();
+        let code_line = snippet.lines().nth(1).unwrap();
+        assert_eq!(&code_line[caret_line.find('^').unwrap()..][..1], "\"");
+    }
+
+    #[test]
+    fn test_render_snippet_near_edges() {
+        let source = "first\n\tsecond\n";
+        assert_eq!(render_snippet(source, Position::new(1, 1), 3), "1 | first\n  | ^\n2 | \tsecond\n");
+        assert_eq!(render_snippet(source, Position::new(9, 3), 0), "2 | \tsecond\n  | \t ^\n");
+        assert_eq!(render_snippet(source, Position::new(1, 40), 0), "1 | first\n  |      ^\n");
+        assert_eq!(render_snippet("", Position::new(1, 1), 2), "");
+    }
+}
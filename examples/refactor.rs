@@ -0,0 +1,74 @@
+// Token-aware source rewrites for automated fixes
+use crate::lexer::{self, SegmentKind};
+
+/// Renames every whole-word occurrence of an identifier in Rust source
+///
+/// Only code is rewritten: string and char literals, comments, and longer
+/// identifiers containing `from` are left alone, as are lifetimes like `'from`.
+pub fn rename_identifier(source: &str, from: &str, to: &str) -> String {
+    let mut renamed = String::with_capacity(source.len());
+    for segment in lexer::segments(source) {
+        let text = &source[segment.start..segment.end];
+        if segment.kind == SegmentKind::Code {
+            rename_in_code(text, from, to, &mut renamed);
+        } else {
+            renamed.push_str(text);
+        }
+    }
+    renamed
+}
+
+fn rename_in_code(code: &str, from: &str, to: &str, out: &mut String) {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let mut rest = code;
+    let mut previous: Option<char> = None;
+
+    while let Some(c) = rest.chars().next() {
+        let len = if is_ident(c) {
+            rest.find(|c: char| !is_ident(c)).unwrap_or(rest.len())
+        } else {
+            c.len_utf8()
+        };
+        let token = &rest[..len];
+        if token == from && previous != Some('\'') {
+            out.push_str(to);
+        } else {
+            out.push_str(token);
+        }
+        previous = token.chars().next_back();
+        rest = &rest[len..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rename_whole_identifiers_only() {
+        let source = r#"fn foo(foobar: u32) -> u32 {
+    // foo is documented here
+    let s = "foo";
+    println!("{}", s);
+    foo(foobar) + self.foo + foo_1
+}
+"#;
+        let expected = r#"fn bar(foobar: u32) -> u32 {
+    // foo is documented here
+    let s = "foo";
+    println!("{}", s);
+    bar(foobar) + self.bar + foo_1
+}
+"#;
+        assert_eq!(rename_identifier(source, "foo", "bar"), expected);
+    }
+
+    #[test]
+    fn test_rename_skips_lifetimes_and_literals() {
+        let source = "fn f<'a>(a: &'a str) -> char { let c = 'a'; /* a */ a.chars().next().unwrap_or(c) }";
+        assert_eq!(
+            rename_identifier(source, "a", "text"),
+            "fn f<'a>(text: &'a str) -> char { let c = 'a'; /* a */ text.chars().next().unwrap_or(c) }"
+        );
+    }
+}
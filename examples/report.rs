@@ -0,0 +1,339 @@
+// Machine-readable reports combining diagnostics, markers, and stats
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::diagnostics::{Diagnostic, Level};
+use crate::markers::MarkerHit;
+use crate::position::Position;
+use crate::Stats;
+
+/// Schema URI written to SARIF logs
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// SARIF rule for diagnostics that didn't come from an analyzer
+const COMPILER_RULE: &str = "rustc";
+
+/// SARIF rule for TODO-style markers
+const MARKER_RULE: &str = "markers";
+
+/// Feedback gathered from several sources, serialized as one JSON document
+///
+/// Markers are grouped by the file they were found in. Serialization sorts
+/// diagnostics by file then position, and markers by position within each
+/// file, so the output is stable regardless of the order entries were added.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FeedbackReport {
+    pub diagnostics: Vec<Diagnostic>,
+    pub markers: BTreeMap<String, Vec<MarkerHit>>,
+    pub stats: Stats,
+}
+
+impl FeedbackReport {
+    /// Creates an empty report for the given processing stats
+    pub fn new(stats: Stats) -> Self {
+        FeedbackReport {
+            stats,
+            ..Default::default()
+        }
+    }
+
+    /// Adds compiler diagnostics to the report
+    pub fn add_diagnostics<I: IntoIterator<Item = Diagnostic>>(&mut self, diagnostics: I) {
+        self.diagnostics.extend(diagnostics);
+    }
+
+    /// Adds markers found in a file to the report
+    pub fn add_markers(&mut self, file: &str, hits: Vec<MarkerHit>) {
+        self.markers.entry(file.to_string()).or_default().extend(hits);
+    }
+
+    /// Gets the most severe level in the report, counting markers as notes
+    pub fn worst_level(&self) -> Option<Level> {
+        let markers = self.markers.values().flatten().map(|_| Level::Note);
+        self.diagnostics.iter().map(|d| d.level).chain(markers).max()
+    }
+
+    /// Checks whether any item is at or above the threshold, such as to pick an exit code
+    pub fn should_fail(&self, threshold: Level) -> bool {
+        self.worst_level().is_some_and(|level| level >= threshold)
+    }
+
+    /// Serializes the report as compact JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.sorted()).expect("reports always serialize")
+    }
+
+    /// Serializes the report as indented JSON
+    pub fn to_json_pretty(&self) -> String {
+        serde_json::to_string_pretty(&self.sorted()).expect("reports always serialize")
+    }
+
+    /// Splits the report into items for streaming, in `to_json` order
+    ///
+    /// Diagnostics come first, then markers grouped by file, then the stats.
+    pub fn into_items(self) -> impl Iterator<Item = FeedbackItem> {
+        let report = self.sorted();
+        let markers = report.markers.into_iter().flat_map(|(file, hits)| {
+            hits.into_iter().map(move |hit| FeedbackItem::Marker { file: file.clone(), hit })
+        });
+        report
+            .diagnostics
+            .into_iter()
+            .map(FeedbackItem::Diagnostic)
+            .chain(markers)
+            .chain(std::iter::once(FeedbackItem::Stats(report.stats)))
+    }
+
+    /// Serializes the report as a SARIF 2.1.0 log for code scanning tools
+    ///
+    /// Each diagnostic becomes a result whose rule is the analyzer that
+    /// reported it, or `rustc` for compiler output, and each marker becomes a
+    /// note under the `markers` rule. Results are ordered as in `to_json`.
+    pub fn to_sarif(&self) -> String {
+        let report = self.sorted();
+        let mut results: Vec<(&str, Level, &str, &str, Position)> = Vec::new();
+        for diagnostic in &report.diagnostics {
+            let rule = diagnostic.analyzer.as_deref().unwrap_or(COMPILER_RULE);
+            results.push((rule, diagnostic.level, &diagnostic.message, &diagnostic.file, diagnostic.position));
+        }
+        for (file, hits) in &report.markers {
+            for hit in hits {
+                results.push((MARKER_RULE, Level::Note, &hit.text, file, hit.position));
+            }
+        }
+
+        let rules: Vec<&str> = results
+            .iter()
+            .map(|(rule, ..)| *rule)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let results: Vec<Value> = results
+            .into_iter()
+            .map(|(rule, level, message, file, position)| {
+                json!({
+                    "ruleId": rule,
+                    "ruleIndex": rules.iter().position(|r| *r == rule),
+                    "level": sarif_level(level),
+                    "message": { "text": message },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": file },
+                            "region": {
+                                "startLine": position.line,
+                                "startColumn": position.column,
+                            },
+                        },
+                    }],
+                })
+            })
+            .collect();
+
+        let log = json!({
+            "$schema": SARIF_SCHEMA,
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rules.iter().map(|rule| json!({ "id": rule })).collect::<Vec<_>>(),
+                    },
+                },
+                "results": results,
+            }],
+        });
+        serde_json::to_string_pretty(&log).expect("reports always serialize")
+    }
+
+    fn sorted(&self) -> FeedbackReport {
+        let mut report = self.clone();
+        report
+            .diagnostics
+            .sort_by(|a, b| (&a.file, a.position).cmp(&(&b.file, b.position)));
+        for hits in report.markers.values_mut() {
+            hits.sort_by_key(|hit| hit.position);
+        }
+        report
+    }
+}
+
+/// One entry of a streamed report, tagged with its `type` when serialized
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum FeedbackItem {
+    Diagnostic(Diagnostic),
+    Marker {
+        file: String,
+        #[serde(flatten)]
+        hit: MarkerHit,
+    },
+    Stats(Stats),
+}
+
+/// Writes each item as its own line of JSON
+///
+/// Items are serialized one at a time, so a large report never has to be
+/// held in memory as a single string.
+pub fn write_report_jsonl<W: Write>(report_items: impl Iterator<Item = FeedbackItem>, mut writer: W) -> io::Result<()> {
+    for item in report_items {
+        serde_json::to_writer(&mut writer, &item)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+/// Maps a severity to the closest SARIF result level
+fn sarif_level(level: Level) -> &'static str {
+    match level {
+        Level::Error => "error",
+        Level::Warning => "warning",
+        Level::Note | Level::Help => "note",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn diagnostic(file: &str, line: u32, message: &str) -> Diagnostic {
+        Diagnostic {
+            level: Level::Warning,
+            message: message.to_string(),
+            file: file.to_string(),
+            position: Position::new(line, 5),
+            span_text: "x".to_string(),
+            code: None,
+            analyzer: None,
+        }
+    }
+
+    fn marker(line: u32, text: &str) -> MarkerHit {
+        MarkerHit {
+            kind: "TODO".to_string(),
+            position: Position::new(line, 4),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_report_json_snapshot() {
+        let mut report = FeedbackReport::new(Stats {
+            base_path: "project".to_string(),
+            processed_files_count: 2,
+            files: vec!["src/main.rs".to_string(), "src/lib.rs".to_string()],
+            bytes_read: 120,
+            bytes_written: 0,
+            by_language: HashMap::from([("Rust".to_string(), 2)]),
+        });
+        report.add_diagnostics(vec![
+            diagnostic("src/main.rs", 2, "unused variable"),
+            diagnostic("src/lib.rs", 9, "dead code"),
+            diagnostic("src/lib.rs", 1, "unused import"),
+        ]);
+        report.add_markers("src/main.rs", vec![marker(7, "TODO: b"), marker(3, "TODO: a")]);
+        report.add_markers("src/lib.rs", vec![marker(1, "TODO: c")]);
+
+        assert_eq!(
+            report.to_json(),
+            concat!(
+                r#"{"diagnostics":["#,
+                r#"{"level":"warning","message":"unused import","file":"src/lib.rs","position":{"line":1,"column":5},"span_text":"x"},"#,
+                r#"{"level":"warning","message":"dead code","file":"src/lib.rs","position":{"line":9,"column":5},"span_text":"x"},"#,
+                r#"{"level":"warning","message":"unused variable","file":"src/main.rs","position":{"line":2,"column":5},"span_text":"x"}"#,
+                r#"],"markers":{"#,
+                r#""src/lib.rs":[{"kind":"TODO","position":{"line":1,"column":4},"text":"TODO: c"}],"#,
+                r#""src/main.rs":[{"kind":"TODO","position":{"line":3,"column":4},"text":"TODO: a"},"#,
+                r#"{"kind":"TODO","position":{"line":7,"column":4},"text":"TODO: b"}]"#,
+                r#"},"stats":{"base_path":"project","processed_files_count":2,"#,
+                r#""files":["src/main.rs","src/lib.rs"],"bytes_read":120,"bytes_written":0,"by_language":{"Rust":2}}}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_pretty_json_matches_compact() {
+        let mut report = FeedbackReport::new(Stats::default());
+        report.add_markers("a.rs", vec![marker(2, "TODO: x")]);
+
+        let pretty: serde_json::Value = serde_json::from_str(&report.to_json_pretty()).unwrap();
+        let compact: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(pretty, compact);
+        assert!(report.to_json_pretty().contains("\n  \"markers\": {"));
+    }
+
+    #[test]
+    fn test_sarif_output() {
+        let mut report = FeedbackReport::new(Stats::default());
+        let mut lint = diagnostic("src/lib.rs", 9, "dead code");
+        lint.analyzer = Some("syntax".to_string());
+        lint.level = Level::Error;
+        report.add_diagnostics(vec![diagnostic("src/main.rs", 2, "unused variable"), lint]);
+        report.add_markers("src/lib.rs", vec![marker(1, "TODO: c")]);
+
+        let sarif: serde_json::Value = serde_json::from_str(&report.to_sarif()).unwrap();
+        assert_eq!(sarif["version"], "2.1.0");
+        let run = &sarif["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"], serde_json::json!([{"id": "markers"}, {"id": "rustc"}, {"id": "syntax"}]));
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        let unused = &results[1];
+        assert_eq!(unused["ruleId"], "rustc");
+        assert_eq!(unused["ruleIndex"], 1);
+        assert_eq!(unused["level"], "warning");
+        assert_eq!(unused["message"]["text"], "unused variable");
+        let location = &unused["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "src/main.rs");
+        assert_eq!(location["region"], serde_json::json!({"startLine": 2, "startColumn": 5}));
+        assert_eq!(results[0]["level"], "error");
+        assert_eq!(results[2]["level"], "note");
+    }
+
+    #[test]
+    fn test_write_report_jsonl() {
+        let mut report = FeedbackReport::new(Stats::default());
+        report.add_diagnostics(vec![diagnostic("src/main.rs", 2, "unused variable")]);
+        report.add_markers("src/lib.rs", vec![marker(3, "TODO: b"), marker(1, "TODO: a")]);
+
+        let mut output = Vec::new();
+        write_report_jsonl(report.into_items(), &mut output).unwrap();
+        let lines: Vec<serde_json::Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        let types: Vec<_> = lines.iter().map(|line| line["type"].as_str().unwrap()).collect();
+        assert_eq!(types, vec!["diagnostic", "marker", "marker", "stats"]);
+        assert_eq!(lines[0]["message"], "unused variable");
+        assert_eq!(lines[1]["file"], "src/lib.rs");
+        assert_eq!(lines[1]["text"], "TODO: a");
+        assert_eq!(lines[3]["processed_files_count"], 0);
+    }
+
+    #[test]
+    fn test_should_fail_on_threshold() {
+        let mut report = FeedbackReport::new(Stats::default());
+        assert_eq!(report.worst_level(), None);
+        assert!(!report.should_fail(Level::Help));
+
+        report.add_markers("src/lib.rs", vec![marker(1, "TODO: c")]);
+        assert_eq!(report.worst_level(), Some(Level::Note));
+
+        report.add_diagnostics(vec![diagnostic("src/lib.rs", 2, "unused variable")]);
+        assert_eq!(report.worst_level(), Some(Level::Warning));
+        assert!(!report.should_fail(Level::Error));
+        assert!(report.should_fail(Level::Warning));
+
+        let mut error = diagnostic("src/lib.rs", 3, "mismatched types");
+        error.level = Level::Error;
+        report.add_diagnostics(vec![error]);
+        assert_eq!(report.worst_level(), Some(Level::Error));
+        assert!(report.should_fail(Level::Error));
+    }
+}
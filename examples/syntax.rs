@@ -0,0 +1,106 @@
+// Quick Rust syntax validation without invoking cargo
+use std::fmt;
+use std::path::Path;
+
+use crate::analyzer::Analyzer;
+use crate::diagnostics::{Diagnostic, Level};
+use crate::language::{detect_language, Language};
+use crate::position::Position;
+
+/// A parse error located in the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub position: Position,
+    pub message: String,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+/// Analyzer reporting the first syntax error in a Rust file
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SyntaxChecker;
+
+impl Analyzer for SyntaxChecker {
+    fn name(&self) -> &str {
+        "syntax"
+    }
+
+    fn analyze(&self, path: &Path, source: &str) -> Vec<Diagnostic> {
+        if detect_language(path) != Some(Language::Rust) {
+            return Vec::new();
+        }
+        match validate_rust_syntax(source) {
+            Ok(()) => Vec::new(),
+            Err(err) => vec![Diagnostic {
+                level: Level::Error,
+                message: err.message,
+                file: crate::slash_path(path),
+                position: err.position,
+                span_text: String::new(),
+                code: None,
+                analyzer: None,
+            }],
+        }
+    }
+}
+
+/// Parses Rust source and reports the first syntax error, if any
+pub fn validate_rust_syntax(source: &str) -> Result<(), SyntaxError> {
+    parse_rust(source).map(|_| ())
+}
+
+/// Parses Rust source into a syntax tree with line/column spans
+pub(crate) fn parse_rust(source: &str) -> Result<syn::File, SyntaxError> {
+    // Tokenize first so unbalanced delimiters report where they occur rather
+    // than syn's generic "cannot parse string into token stream"
+    let tokens = source
+        .parse::<proc_macro2::TokenStream>()
+        .map_err(|err| syntax_error(err.span(), "unbalanced delimiter or invalid token".to_string()))?;
+    syn::parse2::<syn::File>(tokens).map_err(|err| syntax_error(err.span(), err.to_string()))
+}
+
+fn syntax_error(span: proc_macro2::Span, message: String) -> SyntaxError {
+    let start = span.start();
+    SyntaxError {
+        position: Position::new(start.line as u32, start.column as u32 + 1),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_code() {
+        let source = "fn main() {\n    println!(\"hello\");\n}\n";
+        assert_eq!(validate_rust_syntax(source), Ok(()));
+    }
+
+    #[test]
+    fn test_missing_closing_brace() {
+        let source = "fn main() {\n    let x = 1;\n    if x > 0 {\n        println!(\"positive\");\n}\n";
+        let err = validate_rust_syntax(source).unwrap_err();
+        // The unclosed delimiter is the opening brace of `main`
+        assert_eq!(err.position, Position::new(1, 11));
+    }
+
+    #[test]
+    fn test_stray_closing_brace() {
+        let err = validate_rust_syntax("fn main() {\n    run();\n    }\n}\n").unwrap_err();
+        assert_eq!(err.position, Position::new(4, 1));
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        let err = validate_rust_syntax("fn main() {\n    let = 5;\n}\n").unwrap_err();
+        assert_eq!(err.position, Position::new(2, 9));
+        assert!(err.message.starts_with("expected"));
+    }
+}
@@ -0,0 +1,191 @@
+// Text encodings and transformations applied when reading and writing files
+use std::borrow::Cow;
+
+use crate::lexer::{self, SegmentKind};
+
+/// Line ending used when writing text files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "lowercase"))]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+    #[default]
+    Preserve,
+}
+
+impl LineEnding {
+    /// Converts every `\n` or `\r\n` in the content to this line ending
+    pub fn apply<'a>(&self, content: &'a str) -> Cow<'a, str> {
+        match self {
+            LineEnding::Preserve => Cow::Borrowed(content),
+            LineEnding::Lf if !content.contains("\r\n") => Cow::Borrowed(content),
+            LineEnding::Lf => Cow::Owned(content.replace("\r\n", "\n")),
+            LineEnding::CrLf => Cow::Owned(content.replace("\r\n", "\n").replace('\n', "\r\n")),
+        }
+    }
+}
+
+/// Replaces tabs in each line's leading indentation with spaces
+///
+/// Tabs advance to the next multiple of `width` columns, so indentation
+/// mixing spaces and tabs keeps its visual width. Lines that start inside a
+/// string literal are left alone, as are tabs after the indentation.
+pub fn expand_leading_tabs(content: &str, width: usize) -> Cow<'_, str> {
+    if !content.contains('\t') {
+        return Cow::Borrowed(content);
+    }
+    let literals: Vec<(usize, usize)> = lexer::segments(content)
+        .into_iter()
+        .filter(|segment| matches!(segment.kind, SegmentKind::Str | SegmentKind::Char))
+        .map(|segment| (segment.start, segment.end))
+        .collect();
+
+    let mut expanded = String::with_capacity(content.len());
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        let in_literal = literals.iter().any(|(start, end)| *start < offset && offset < *end);
+        let indent_len = if in_literal { 0 } else { line.len() - line.trim_start_matches([' ', '\t']).len() };
+        let mut column = 0;
+        for c in line[..indent_len].chars() {
+            let next = match c {
+                '\t' if width > 0 => (column / width + 1) * width,
+                '\t' => column,
+                _ => column + 1,
+            };
+            expanded.extend(std::iter::repeat_n(' ', next - column));
+            column = next;
+        }
+        expanded.push_str(&line[indent_len..]);
+        offset += line.len();
+    }
+    Cow::Owned(expanded)
+}
+
+/// Text encoding used to decode file contents
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Latin1,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Encoding {
+    /// Display name of the encoding
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Latin1 => "ISO-8859-1",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+        }
+    }
+
+    /// Byte order mark that can start text in this encoding, empty for Latin-1
+    pub fn bom(&self) -> &'static [u8] {
+        match self {
+            Encoding::Utf8 => &[0xEF, 0xBB, 0xBF],
+            Encoding::Latin1 => &[],
+            Encoding::Utf16Le => &[0xFF, 0xFE],
+            Encoding::Utf16Be => &[0xFE, 0xFF],
+        }
+    }
+
+    /// Encodes text, returning `None` if it has characters this encoding can't represent
+    pub fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        match self {
+            Encoding::Utf8 => Some(text.as_bytes().to_vec()),
+            Encoding::Latin1 => text.chars().map(|c| u8::try_from(c).ok()).collect(),
+            Encoding::Utf16Le => Some(text.encode_utf16().flat_map(u16::to_le_bytes).collect()),
+            Encoding::Utf16Be => Some(text.encode_utf16().flat_map(u16::to_be_bytes).collect()),
+        }
+    }
+
+    /// Decodes bytes, returning `None` if they are malformed for this encoding
+    ///
+    /// With `lossy` set, malformed sequences become U+FFFD instead.
+    pub fn decode(&self, bytes: &[u8], lossy: bool) -> Option<String> {
+        let encoding = match self {
+            // encoding_rs maps the "latin1" label to windows-1252, so decode
+            // ISO-8859-1 directly: every byte is the code point of the same value
+            Encoding::Latin1 => return Some(bytes.iter().map(|&b| char::from(b)).collect()),
+            Encoding::Utf8 => encoding_rs::UTF_8,
+            Encoding::Utf16Le => encoding_rs::UTF_16LE,
+            Encoding::Utf16Be => encoding_rs::UTF_16BE,
+        };
+        if lossy {
+            let (decoded, _) = encoding.decode_without_bom_handling(bytes);
+            Some(decoded.into_owned())
+        } else {
+            encoding
+                .decode_without_bom_handling_and_without_replacement(bytes)
+                .map(Cow::into_owned)
+        }
+    }
+}
+
+/// The encoding of some text, as guessed from its leading bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DetectedEncoding {
+    pub encoding: Encoding,
+    pub has_bom: bool,
+}
+
+impl DetectedEncoding {
+    /// Guesses the encoding of a sample from the start of some text
+    ///
+    /// A byte order mark decides the encoding outright. Otherwise a sample
+    /// where most bytes at odd or even offsets are zero is taken as UTF-16
+    /// little or big endian, valid UTF-8 as UTF-8, and anything else as
+    /// Latin-1. A multi-byte character cut off at the end of the sample
+    /// doesn't count against UTF-8.
+    pub fn detect(sample: &[u8]) -> DetectedEncoding {
+        let with_bom = [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be]
+            .into_iter()
+            .find(|encoding| sample.starts_with(encoding.bom()));
+        if let Some(encoding) = with_bom {
+            return DetectedEncoding { encoding, has_bom: true };
+        }
+
+        let pairs = sample.len() / 2;
+        let zeros_at = |parity: usize| sample.iter().skip(parity).step_by(2).filter(|&&b| b == 0).count();
+        let encoding = if pairs > 0 && zeros_at(1) * 2 > pairs && zeros_at(0) * 2 <= pairs {
+            Encoding::Utf16Le
+        } else if pairs > 0 && zeros_at(0) * 2 > pairs && zeros_at(1) * 2 <= pairs {
+            Encoding::Utf16Be
+        } else {
+            match std::str::from_utf8(sample) {
+                Ok(_) => Encoding::Utf8,
+                Err(err) if err.error_len().is_none() => Encoding::Utf8,
+                Err(_) => Encoding::Latin1,
+            }
+        };
+        DetectedEncoding { encoding, has_bom: false }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_without_bom() {
+        let detect = |bytes: &[u8]| DetectedEncoding::detect(bytes).encoding;
+        assert_eq!(detect(b"h\x00i\x00"), Encoding::Utf16Le);
+        assert_eq!(detect(b"\x00h\x00i"), Encoding::Utf16Be);
+        assert_eq!(detect("café".as_bytes()), Encoding::Utf8);
+        assert_eq!(detect(&"café".as_bytes()[..4]), Encoding::Utf8);
+        assert_eq!(detect(b"caf\xe9!"), Encoding::Latin1);
+        assert_eq!(detect(b""), Encoding::Utf8);
+    }
+
+    #[test]
+    fn test_encode_round_trips() {
+        for encoding in [Encoding::Utf8, Encoding::Latin1, Encoding::Utf16Le, Encoding::Utf16Be] {
+            let bytes = encoding.encode("café\n").unwrap();
+            assert_eq!(encoding.decode(&bytes, false).as_deref(), Some("café\n"));
+        }
+        assert_eq!(Encoding::Latin1.encode("caf\u{e9}"), Some(b"caf\xe9".to_vec()));
+        assert_eq!(Encoding::Latin1.encode("snow \u{2603}"), None);
+    }
+}
@@ -0,0 +1,299 @@
+// All-or-nothing application of several file writes and deletes
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::error::{self, FileProcessorError};
+use crate::{slash_path, temp_sibling, FileProcessor};
+
+/// Writes and deletes buffered until `commit` applies them together
+///
+/// Obtained from `FileProcessor::begin`. Nothing touches disk until commit;
+/// dropping the transaction or calling `abort` discards it.
+pub struct Transaction<'a> {
+    processor: &'a mut FileProcessor,
+    /// Pending operations by normalized path, with `None` marking a delete
+    pending: Vec<(String, Option<String>)>,
+}
+
+/// One operation being committed, tracking what has happened on disk so it can be undone
+struct Staged {
+    name: String,
+    path: PathBuf,
+    content: Option<String>,
+    temp: Option<PathBuf>,
+    saved: Option<PathBuf>,
+    placed: bool,
+}
+
+impl<'a> Transaction<'a> {
+    pub(crate) fn new(processor: &'a mut FileProcessor) -> Self {
+        Transaction {
+            processor,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Stages a write, replacing anything already staged for the path
+    pub fn write<P: AsRef<Path>>(&mut self, filename: P, content: &str) {
+        self.stage(filename.as_ref(), Some(content.to_string()));
+    }
+
+    /// Stages a delete, replacing anything already staged for the path
+    pub fn delete<P: AsRef<Path>>(&mut self, filename: P) {
+        self.stage(filename.as_ref(), None);
+    }
+
+    fn stage(&mut self, filename: &Path, content: Option<String>) {
+        let name = slash_path(filename);
+        match self.pending.iter_mut().find(|(pending, _)| *pending == name) {
+            Some(entry) => entry.1 = content,
+            None => self.pending.push((name, content)),
+        }
+    }
+
+    /// Reads a file as it will be after commit, including staged changes
+    pub fn read<P: AsRef<Path>>(&mut self, filename: P) -> error::Result<String> {
+        let name = slash_path(filename.as_ref());
+        match self.pending.iter().find(|(pending, _)| *pending == name) {
+            Some((_, Some(content))) => Ok(content.clone()),
+            Some((_, None)) => Err(FileProcessorError::NotFound(filename.as_ref().to_path_buf())),
+            None => self.processor.read_file(filename),
+        }
+    }
+
+    /// Discards every staged change
+    pub fn abort(self) {}
+
+    /// Applies every staged change, or none of them if any step fails
+    ///
+    /// All paths are validated first, then new content is written to temp
+    /// files. With every path locked and existing files backed up as in
+    /// `write_file`, originals are moved aside and temps renamed into place.
+    /// On failure, changes already made are undone on a best-effort basis,
+    /// though backups are kept. In dry-run mode the writes and deletes are
+    /// only recorded as planned.
+    pub fn commit(self) -> error::Result<()> {
+        let processor = self.processor;
+        if processor.dry_run {
+            for (name, content) in &self.pending {
+                let path = processor.resolve_path(name)?;
+                match content {
+                    Some(content) => {
+                        let content = processor.prepare_content(content).into_owned();
+                        processor.plan_write(Path::new(name), &path, content.len(), &content);
+                    }
+                    None if !path.is_file() => return Err(FileProcessorError::NotFound(path)),
+                    None => processor.planned_deletes.push(name.clone()),
+                }
+            }
+            return Ok(());
+        }
+
+        let mut staged = Vec::new();
+        for (name, content) in self.pending {
+            let path = processor.resolve_path(&name)?;
+            if content.is_none() && !path.is_file() {
+                return Err(FileProcessorError::NotFound(path));
+            }
+            let content = content.map(|c| processor.prepare_content(&c).into_owned());
+            staged.push(Staged { name, path, content, temp: None, saved: None, placed: false });
+        }
+
+        if let Err(err) = apply(processor, &mut staged) {
+            roll_back(&staged);
+            return Err(err);
+        }
+
+        for operation in staged {
+            if let Some(saved) = &operation.saved {
+                let _ = fs::remove_file(saved);
+            }
+            processor.invalidate_cache(&operation.path);
+            match &operation.content {
+                Some(content) => {
+                    processor.bytes_written += content.len() as u64;
+                    processor.record_file(Path::new(&operation.name));
+                }
+                None => processor.deleted_files.push(operation.name),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn apply(processor: &mut FileProcessor, staged: &mut [Staged]) -> error::Result<()> {
+    for operation in staged.iter_mut() {
+        let Some(content) = &operation.content else {
+            continue;
+        };
+        processor.prepare_parent(&operation.path)?;
+        let temp = temp_sibling(&operation.path);
+        operation.temp = Some(temp.clone());
+        File::create(&temp)
+            .and_then(|mut file| {
+                file.write_all(content.as_bytes())?;
+                file.sync_all()
+            })
+            .map_err(|e| FileProcessorError::from_io(e, &operation.path))?;
+    }
+
+    let mut locks = Vec::new();
+    for operation in staged.iter() {
+        locks.push(processor.lock_for_write(&operation.path)?);
+    }
+    for operation in staged.iter() {
+        processor.backup_existing(Path::new(&operation.name), &operation.path)?;
+    }
+
+    for operation in staged.iter_mut() {
+        let io_error = |e| FileProcessorError::from_io(e, &operation.path);
+        // A directory in the way makes the rename below fail rather than being moved aside
+        if operation.path.is_file() {
+            let saved = temp_sibling(&operation.path);
+            fs::rename(&operation.path, &saved).map_err(io_error)?;
+            operation.saved = Some(saved);
+        }
+        if let Some(temp) = &operation.temp {
+            fs::rename(temp, &operation.path).map_err(io_error)?;
+            operation.placed = true;
+        }
+    }
+    Ok(())
+}
+
+fn roll_back(staged: &[Staged]) {
+    for operation in staged.iter().rev() {
+        if operation.placed {
+            let _ = fs::remove_file(&operation.path);
+        } else if let Some(temp) = &operation.temp {
+            let _ = fs::remove_file(temp);
+        }
+        if let Some(saved) = &operation.saved {
+            let _ = fs::rename(saved, &operation.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("transaction_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_abort_discards_writes() {
+        let dir = temp_dir("abort");
+        fs::write(dir.join("a.rs"), "old a").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        let mut transaction = processor.begin();
+        transaction.write("a.rs", "new a");
+        transaction.write("b.rs", "new b");
+        assert_eq!(transaction.read("a.rs").unwrap(), "new a");
+        assert_eq!(transaction.read("b.rs").unwrap(), "new b");
+        transaction.abort();
+
+        assert_eq!(fs::read_to_string(dir.join("a.rs")).unwrap(), "old a");
+        assert!(!dir.join("b.rs").exists());
+    }
+
+    #[test]
+    fn test_commit_applies_all_changes() {
+        let dir = temp_dir("commit");
+        fs::write(dir.join("a.rs"), "old a").unwrap();
+        fs::write(dir.join("stale.rs"), "stale").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        let mut transaction = processor.begin();
+        transaction.write("a.rs", "new a");
+        transaction.write("b.rs", "new b");
+        transaction.delete("stale.rs");
+        assert!(transaction.read("stale.rs").is_err());
+        transaction.commit().unwrap();
+
+        assert_eq!(fs::read_to_string(dir.join("a.rs")).unwrap(), "new a");
+        assert_eq!(fs::read_to_string(dir.join("b.rs")).unwrap(), "new b");
+        assert!(!dir.join("stale.rs").exists());
+        assert_eq!(processor.stats().files, vec!["a.rs", "b.rs"]);
+        assert_eq!(processor.deleted_files(), ["stale.rs"]);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_failed_commit_rolls_back() {
+        let dir = temp_dir("rollback");
+        fs::write(dir.join("a.rs"), "old a").unwrap();
+        fs::write(dir.join("blocked"), "a file, not a directory").unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        let mut transaction = processor.begin();
+        transaction.write("a.rs", "new a");
+        transaction.write("blocked/b.rs", "new b");
+        assert!(transaction.commit().is_err());
+
+        assert_eq!(fs::read_to_string(dir.join("a.rs")).unwrap(), "old a");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        assert!(processor.stats().files.is_empty());
+    }
+
+    #[test]
+    fn test_failed_rename_restores_placed_files() {
+        let dir = temp_dir("rollback_rename");
+        fs::write(dir.join("a.rs"), "old a").unwrap();
+        fs::create_dir(dir.join("sub")).unwrap();
+        let mut processor = FileProcessor::new(&dir);
+
+        let mut transaction = processor.begin();
+        transaction.write("a.rs", "new a");
+        transaction.write("sub", "not a directory");
+        assert!(transaction.commit().is_err());
+
+        assert_eq!(fs::read_to_string(dir.join("a.rs")).unwrap(), "old a");
+        assert!(dir.join("sub").is_dir());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        assert!(processor.stats().files.is_empty());
+    }
+
+    #[test]
+    fn test_commit_backs_up_originals() {
+        let dir = temp_dir("backup");
+        fs::write(dir.join("a.rs"), "old a").unwrap();
+        fs::write(dir.join("stale.rs"), "stale").unwrap();
+        let mut processor = FileProcessor::new(&dir).with_backup(true);
+
+        let mut transaction = processor.begin();
+        transaction.write("a.rs", "new a");
+        transaction.write("b.rs", "new b");
+        transaction.delete("stale.rs");
+        transaction.commit().unwrap();
+
+        assert_eq!(fs::read_to_string(dir.join("a.rs")).unwrap(), "new a");
+        assert_eq!(fs::read_to_string(dir.join("a.rs.bak")).unwrap(), "old a");
+        assert_eq!(fs::read_to_string(dir.join("stale.rs.bak")).unwrap(), "stale");
+        assert!(!dir.join("stale.rs").exists());
+        assert!(!dir.join("b.rs.bak").exists());
+    }
+
+    #[test]
+    fn test_dry_run_commit_plans_changes() {
+        let dir = temp_dir("dry_run");
+        fs::write(dir.join("stale.rs"), "stale").unwrap();
+        let mut processor = FileProcessor::new(&dir).with_dry_run(true);
+
+        let mut transaction = processor.begin();
+        transaction.write("a.rs", "new a");
+        transaction.delete("stale.rs");
+        transaction.commit().unwrap();
+
+        assert!(!dir.join("a.rs").exists());
+        assert!(dir.join("stale.rs").exists());
+        assert_eq!(processor.planned_writes(), [("a.rs".to_string(), 5)]);
+        assert_eq!(processor.planned_deletes(), ["stale.rs"]);
+    }
+}
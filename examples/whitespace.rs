@@ -0,0 +1,232 @@
+// Whitespace style checks such as trailing spaces, final newlines, indentation, and line length
+
+/// Columns between tab stops used by `long_lines`
+pub const DEFAULT_TAB_WIDTH: usize = 4;
+
+/// How lines in a file are indented
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Indentation {
+    /// No line is indented
+    #[default]
+    None,
+    Spaces,
+    Tabs,
+    /// Both tabs and spaces are used, in the same line or across lines
+    Mixed,
+}
+
+/// Whitespace problems found in a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WhitespaceReport {
+    /// 1-based numbers of lines ending in spaces or tabs
+    pub trailing_whitespace: Vec<usize>,
+    pub ends_with_newline: bool,
+    pub indentation: Indentation,
+}
+
+impl WhitespaceReport {
+    /// Checks whether the file has no whitespace issues
+    pub fn is_clean(&self) -> bool {
+        self.trailing_whitespace.is_empty() && self.ends_with_newline && self.indentation != Indentation::Mixed
+    }
+}
+
+/// Reports trailing whitespace, a missing final newline, and mixed indentation
+///
+/// Empty files count as ending with a newline, and the `\r` of a `\r\n`
+/// line ending isn't treated as trailing whitespace.
+pub fn whitespace_issues(source: &str) -> WhitespaceReport {
+    let mut trailing_whitespace = Vec::new();
+    let (mut spaces, mut tabs) = (false, false);
+
+    for (index, line) in source.lines().enumerate() {
+        if line.ends_with([' ', '\t']) {
+            trailing_whitespace.push(index + 1);
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let indent = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+        spaces |= indent.contains(' ');
+        tabs |= indent.contains('\t');
+    }
+
+    WhitespaceReport {
+        trailing_whitespace,
+        ends_with_newline: source.is_empty() || source.ends_with('\n'),
+        indentation: match (spaces, tabs) {
+            (false, false) => Indentation::None,
+            (true, false) => Indentation::Spaces,
+            (false, true) => Indentation::Tabs,
+            (true, true) => Indentation::Mixed,
+        },
+    }
+}
+
+/// The indentation style most lines of a file follow
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IndentStyle {
+    /// No line is indented
+    #[default]
+    None,
+    /// Spaces, with the narrowest indentation used as the width of one level
+    Spaces(usize),
+    Tabs,
+    /// Tabs and spaces are used by equally many lines, or most lines use both at once
+    Mixed,
+}
+
+/// How consistently a file follows its dominant indentation style
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndentReport {
+    pub style: IndentStyle,
+    /// 1-based numbers of indented lines that don't use the dominant style
+    pub offending_lines: Vec<usize>,
+}
+
+impl IndentReport {
+    /// Number of indented lines using a style other than the dominant one
+    pub fn minority_count(&self) -> usize {
+        self.offending_lines.len()
+    }
+}
+
+/// Finds the dominant indentation style and the lines that depart from it
+///
+/// Blank and unindented lines are ignored. Each indented line uses spaces,
+/// tabs, or both, and the style used by the most lines is dominant. When
+/// no style has the most lines, or lines using both win, the style is
+/// `Mixed` and every line that isn't indented with both is offending.
+pub fn indentation_report(source: &str) -> IndentReport {
+    let (mut spaces, mut tabs, mut both) = (Vec::new(), Vec::new(), Vec::new());
+    let mut width = usize::MAX;
+
+    for (index, line) in source.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let indent = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+        match (indent.contains(' '), indent.contains('\t')) {
+            (false, false) => {}
+            (true, false) => {
+                spaces.push(index + 1);
+                width = width.min(indent.len());
+            }
+            (false, true) => tabs.push(index + 1),
+            (true, true) => both.push(index + 1),
+        }
+    }
+
+    let (style, mut offending_lines) = if spaces.len() > tabs.len().max(both.len()) {
+        (IndentStyle::Spaces(width), [tabs, both].concat())
+    } else if tabs.len() > spaces.len().max(both.len()) {
+        (IndentStyle::Tabs, [spaces, both].concat())
+    } else if spaces.is_empty() && tabs.is_empty() && both.is_empty() {
+        (IndentStyle::None, Vec::new())
+    } else {
+        (IndentStyle::Mixed, [spaces, tabs].concat())
+    };
+    offending_lines.sort_unstable();
+    IndentReport { style, offending_lines }
+}
+
+/// Finds lines wider than `max` columns, as `(line_number, width)` pairs
+///
+/// Tabs advance to the next multiple of `DEFAULT_TAB_WIDTH` columns, and
+/// every other character counts as one column.
+pub fn long_lines(source: &str, max: usize) -> Vec<(usize, usize)> {
+    long_lines_with_tab_width(source, max, DEFAULT_TAB_WIDTH)
+}
+
+/// Finds lines wider than `max` columns with tab stops every `tab_width` columns
+pub fn long_lines_with_tab_width(source: &str, max: usize, tab_width: usize) -> Vec<(usize, usize)> {
+    source
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, display_width(line, tab_width)))
+        .filter(|(_, width)| *width > max)
+        .collect()
+}
+
+fn display_width(line: &str, tab_width: usize) -> usize {
+    line.chars().fold(0, |width, c| match c {
+        '\t' if tab_width > 0 => (width / tab_width + 1) * tab_width,
+        '\t' => width,
+        _ => width + 1,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trailing_whitespace() {
+        let report = whitespace_issues("fn main() { \n    run();\t\n}\r\n");
+        assert_eq!(report.trailing_whitespace, vec![1, 2]);
+        assert!(report.ends_with_newline);
+        assert_eq!(report.indentation, Indentation::Spaces);
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn test_missing_final_newline() {
+        let report = whitespace_issues("fn main() {}");
+        assert!(!report.ends_with_newline);
+        assert!(report.trailing_whitespace.is_empty());
+        assert_eq!(report.indentation, Indentation::None);
+        assert!(whitespace_issues("").ends_with_newline);
+    }
+
+    #[test]
+    fn test_mixed_indentation() {
+        assert_eq!(whitespace_issues("a\n\tb\n    c\n").indentation, Indentation::Mixed);
+        assert_eq!(whitespace_issues("a\n\t  b\n").indentation, Indentation::Mixed);
+        assert_eq!(whitespace_issues("a\n\tb\n\n\t\tc\n").indentation, Indentation::Tabs);
+        assert!(whitespace_issues("a\n\tb\n").is_clean());
+    }
+
+    #[test]
+    fn test_long_lines() {
+        let source = "short\nthis line is too long\r\nok\n";
+        assert_eq!(long_lines(source, 10), vec![(2, 21)]);
+        assert!(long_lines(source, 21).is_empty());
+    }
+
+    #[test]
+    fn test_long_lines_expand_tabs() {
+        let source = "\tab\n\t\tx\n";
+        assert_eq!(long_lines(source, 6), vec![(2, 9)]);
+        assert_eq!(long_lines_with_tab_width(source, 6, 8), vec![(1, 10), (2, 17)]);
+        assert!(long_lines_with_tab_width(source, 6, 2).is_empty());
+        assert_eq!(long_lines("ab\tc", 4), vec![(1, 5)]);
+    }
+
+    #[test]
+    fn test_indentation_report_spaces() {
+        let report = indentation_report("fn main() {\n    if ok {\n        run();\n    }\n\n}\n");
+        assert_eq!(report.style, IndentStyle::Spaces(4));
+        assert_eq!(report.minority_count(), 0);
+        assert_eq!(indentation_report("fn main() {}\n"), IndentReport::default());
+    }
+
+    #[test]
+    fn test_indentation_report_tabs() {
+        let report = indentation_report("fn main() {\n\tif ok {\n\t\trun();\n\t}\n}\n");
+        assert_eq!(report.style, IndentStyle::Tabs);
+        assert!(report.offending_lines.is_empty());
+    }
+
+    #[test]
+    fn test_indentation_report_lists_outliers() {
+        let source = "fn main() {\n  a();\n\tb();\n  c();\n\t  d();\n  e();\n}\n";
+        let report = indentation_report(source);
+        assert_eq!(report.style, IndentStyle::Spaces(2));
+        assert_eq!(report.offending_lines, vec![3, 5]);
+        assert_eq!(report.minority_count(), 2);
+
+        let tied = indentation_report("a\n\tb\n    c\n\t  d\n");
+        assert_eq!(tied.style, IndentStyle::Mixed);
+        assert_eq!(tied.offending_lines, vec![2, 3]);
+    }
+}